@@ -2,43 +2,235 @@
 //!
 //! Features:
 //! - Network-level blocking via Brave's adblock-rust engine
-//! - Cosmetic filtering (element hiding via CSS)
+//! - Cosmetic filtering (element hiding via CSS), plus elements the user blocked
 //! - YouTube ad-skip scriptlet injection
 //! - Multiple filter lists including YouTube-specific blockers
+//! - Tracker categories: one engine per category, each can be turned off;
+//!   exception rules from every list apply in every engine
 
 use adblock::Engine;
 use adblock::lists::{FilterSet, ParseOptions};
+use serde::{Serialize, Deserialize};
 use std::cell::RefCell;
 use std::path::PathBuf;
 use std::fs;
 use tracing::{info, warn};
 
-/// Filter lists to download - expanded for better coverage
-const FILTER_LISTS: &[(&str, &str)] = &[
+use crate::omnibox::host_of;
+
+/// What kind of tracking a blocked request belongs to
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackerCategory {
+    Advertising,
+    Analytics,
+    Social,
+    Fingerprinting,
+    Cryptomining,
+    Annoyances,
+}
+
+impl TrackerCategory {
+    /// Every category, in display order
+    pub const ALL: [TrackerCategory; 6] = [
+        TrackerCategory::Advertising,
+        TrackerCategory::Analytics,
+        TrackerCategory::Social,
+        TrackerCategory::Fingerprinting,
+        TrackerCategory::Cryptomining,
+        TrackerCategory::Annoyances,
+    ];
+
+    /// Stable identifier (used in URLs and statistics)
+    pub fn name(self) -> &'static str {
+        match self {
+            TrackerCategory::Advertising => "advertising",
+            TrackerCategory::Analytics => "analytics",
+            TrackerCategory::Social => "social",
+            TrackerCategory::Fingerprinting => "fingerprinting",
+            TrackerCategory::Cryptomining => "cryptomining",
+            TrackerCategory::Annoyances => "annoyances",
+        }
+    }
+
+    /// Human-readable name
+    pub fn label(self) -> &'static str {
+        match self {
+            TrackerCategory::Advertising => "Advertising",
+            TrackerCategory::Analytics => "Analytics",
+            TrackerCategory::Social => "Social widgets",
+            TrackerCategory::Fingerprinting => "Fingerprinting",
+            TrackerCategory::Cryptomining => "Cryptomining",
+            TrackerCategory::Annoyances => "Annoyances",
+        }
+    }
+
+    /// Parse a stable identifier
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+/// Filter lists to download - expanded for better coverage.
+/// The category says what the list blocks; `None` marks exception lists
+/// that are added to every category's engine.
+const FILTER_LISTS: &[(&str, &str, Option<TrackerCategory>)] = &[
     // Core lists
-    ("easylist", "https://easylist.to/easylist/easylist.txt"),
-    ("easyprivacy", "https://easylist.to/easylist/easyprivacy.txt"),
+    ("easylist", "https://easylist.to/easylist/easylist.txt", Some(TrackerCategory::Advertising)),
+    ("easyprivacy", "https://easylist.to/easylist/easyprivacy.txt", Some(TrackerCategory::Analytics)),
     
     // uBlock Origin lists
-    ("ublock-ads", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/filters.txt"),
-    ("ublock-privacy", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/privacy.txt"),
-    ("ublock-quick", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/quick-fixes.txt"),
-    ("ublock-unbreak", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/unbreak.txt"),
+    ("ublock-ads", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/filters.txt", Some(TrackerCategory::Advertising)),
+    ("ublock-privacy", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/privacy.txt", Some(TrackerCategory::Analytics)),
+    ("ublock-quick", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/quick-fixes.txt", Some(TrackerCategory::Advertising)),
+    ("ublock-unbreak", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/unbreak.txt", None),
     
-    // YouTube/Google specific
-    ("ublock-badware", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/badware.txt"),
+    // YouTube/Google specific (malvertising and scam domains)
+    ("ublock-badware", "https://raw.githubusercontent.com/uBlockOrigin/uAssets/master/filters/badware.txt", Some(TrackerCategory::Advertising)),
     
     // Peter Lowe's list
-    ("peter-lowe", "https://pgl.yoyo.org/adservers/serverlist.php?hostformat=adblockplus&showintro=1&mimetype=plaintext"),
+    ("peter-lowe", "https://pgl.yoyo.org/adservers/serverlist.php?hostformat=adblockplus&showintro=1&mimetype=plaintext", Some(TrackerCategory::Advertising)),
+    
+    // Cryptominers
+    ("nocoin", "https://raw.githubusercontent.com/hoshsadiq/adblock-nocoin-list/master/nocoin.txt", Some(TrackerCategory::Cryptomining)),
     
     // Annoyances
-    ("fanboy-annoyance", "https://secure.fanboy.co.nz/fanboy-annoyance.txt"),
-    ("fanboy-social", "https://easylist.to/easylist/fanboy-social.txt"),
+    ("fanboy-annoyance", "https://secure.fanboy.co.nz/fanboy-annoyance.txt", Some(TrackerCategory::Annoyances)),
+    ("fanboy-social", "https://easylist.to/easylist/fanboy-social.txt", Some(TrackerCategory::Social)),
+];
+
+/// Built-in fingerprinting rules (no maintained list exists in ABP syntax)
+const FINGERPRINTING_RULES: &[&str] = &[
+    "||fpjs.io^$third-party",
+    "||fpcdn.io^$third-party",
+    "||openfpcdn.io^$third-party",
+    "||fpnpmcdn.net^$third-party",
+    "/fingerprint2.min.js$script,third-party",
+    "/fingerprintjs2.min.js$script,third-party",
+    "/clientjs/dist/client.min.js$script,third-party",
 ];
 
-// Thread-local engine (since we're running single-threaded GTK)
+// Thread-local engines, one per category (since we're running single-threaded GTK)
 thread_local! {
-    static ADBLOCK_ENGINE: RefCell<Option<Engine>> = const { RefCell::new(None) };
+    static ADBLOCK_ENGINES: RefCell<Vec<(TrackerCategory, Engine)>> = const { RefCell::new(Vec::new()) };
+    static PREFS: RefCell<BlockingPrefs> = RefCell::new(BlockingPrefs::load());
+}
+
+/// Which tracker categories and sites the user has exempted from blocking
+#[derive(Serialize, Deserialize, Default)]
+pub struct BlockingPrefs {
+    disabled: Vec<TrackerCategory>,
+    /// Sites (and their subdomains) where nothing is blocked
+    #[serde(default)]
+    allowed_sites: Vec<String>,
+    /// Elements hidden with "Block Element" from the page's context menu
+    #[serde(default)]
+    hidden_elements: Vec<HiddenElement>,
+}
+
+/// A cosmetic rule added by the user: hide `selector` on `site`
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct HiddenElement {
+    pub site: String,
+    pub selector: String,
+}
+
+/// Run a closure with the blocking preferences
+pub fn with_blocking_prefs<R>(f: impl FnOnce(&mut BlockingPrefs) -> R) -> R {
+    PREFS.with(|prefs| f(&mut prefs.borrow_mut()))
+}
+
+impl BlockingPrefs {
+    /// Load preferences from the data directory
+    pub fn load() -> Self {
+        let path = crate::webview::get_data_dir().join("blocking.json");
+        if let Ok(data) = fs::read_to_string(&path) {
+            serde_json::from_str(&data).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Save preferences to the data directory
+    pub fn save(&self) {
+        let path = crate::webview::get_data_dir().join("blocking.json");
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            fs::write(path, json).ok();
+        }
+    }
+
+    /// Whether requests in a category are blocked
+    pub fn is_enabled(&self, category: TrackerCategory) -> bool {
+        !self.disabled.contains(&category)
+    }
+
+    /// Turn blocking for a category on or off
+    pub fn set_enabled(&mut self, category: TrackerCategory, enabled: bool) {
+        self.disabled.retain(|c| *c != category);
+        if !enabled {
+            self.disabled.push(category);
+        }
+    }
+
+    /// Sites where blocking is turned off
+    pub fn allowed_sites(&self) -> &[String] {
+        &self.allowed_sites
+    }
+
+    /// Whether blocking is turned off for the site of a page URL
+    pub fn is_site_allowed(&self, page_url: &str) -> bool {
+        let host = host_of(page_url).to_lowercase();
+        !host.is_empty() && self.allowed_sites.iter().any(|site| {
+            host == *site || host.strip_suffix(site.as_str()).is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+
+    /// Turn blocking off (allowed) or back on for a site, given a host or URL
+    pub fn set_site_allowed(&mut self, site: &str, allowed: bool) {
+        let site = host_of(site.trim()).to_lowercase();
+        if site.is_empty() {
+            return;
+        }
+        self.allowed_sites.retain(|s| *s != site);
+        if allowed {
+            self.allowed_sites.push(site);
+            self.allowed_sites.sort();
+        }
+    }
+
+    /// Cosmetic rules added by the user, by site
+    pub fn hidden_elements(&self) -> &[HiddenElement] {
+        &self.hidden_elements
+    }
+
+    /// Hide elements matching `selector` on the site of a page URL
+    pub fn hide_element(&mut self, page_url: &str, selector: &str) {
+        let rule = HiddenElement {
+            site: host_of(page_url).to_lowercase(),
+            selector: selector.trim().to_string(),
+        };
+        if rule.site.is_empty() || rule.selector.is_empty() || self.hidden_elements.contains(&rule) {
+            return;
+        }
+        self.hidden_elements.push(rule);
+        self.hidden_elements.sort_by(|a, b| a.site.cmp(&b.site));
+    }
+
+    /// Remove a user cosmetic rule; returns whether it existed
+    pub fn unhide_element(&mut self, site: &str, selector: &str) -> bool {
+        let before = self.hidden_elements.len();
+        self.hidden_elements.retain(|r| r.site != site || r.selector != selector);
+        self.hidden_elements.len() != before
+    }
+
+    /// Selectors the user hid on the site of a page URL
+    fn hidden_selectors(&self, page_url: &str) -> Vec<String> {
+        let host = host_of(page_url).to_lowercase();
+        self.hidden_elements.iter()
+            .filter(|r| host == r.site)
+            .map(|r| r.selector.clone())
+            .collect()
+    }
 }
 
 /// Get the filter cache directory
@@ -51,13 +243,17 @@ fn get_filter_dir() -> PathBuf {
     dir
 }
 
-/// Create the adblock engine with filter lists
-fn create_engine() -> Engine {
+/// Create one adblock engine per tracker category from the filter lists.
+/// Exception rules (`@@`, `#@#`) go into every engine, so an exception in
+/// one list still un-blocks what another list's category matches.
+fn create_engines() -> Vec<(TrackerCategory, Engine)> {
     let filter_dir = get_filter_dir();
-    let mut filter_set = FilterSet::new(false);
+    let mut filter_sets: Vec<(TrackerCategory, FilterSet)> = TrackerCategory::ALL.into_iter()
+        .map(|category| (category, FilterSet::new(false)))
+        .collect();
     let mut total_rules = 0;
     
-    for (name, url) in FILTER_LISTS {
+    for (name, url, list_category) in FILTER_LISTS {
         let cache_path = filter_dir.join(format!("{}.txt", name));
         
         // Try to load from cache first
@@ -86,15 +282,36 @@ fn create_engine() -> Engine {
         
         if !content.is_empty() {
             let rules: Vec<&str> = content.lines().collect();
+            let exceptions: Vec<&str> = rules.iter().copied().filter(|r| is_exception(r)).collect();
             let count = rules.len();
-            filter_set.add_filters(&rules, ParseOptions::default());
+            for (category, filter_set) in &mut filter_sets {
+                if list_category.is_none_or(|c| c == *category) {
+                    filter_set.add_filters(&rules, ParseOptions::default());
+                } else if !exceptions.is_empty() {
+                    filter_set.add_filters(&exceptions, ParseOptions::default());
+                }
+            }
             total_rules += count;
             info!("Loaded {} rules from {}", count, name);
         }
     }
+
+    for (category, filter_set) in &mut filter_sets {
+        if *category == TrackerCategory::Fingerprinting {
+            filter_set.add_filters(FINGERPRINTING_RULES, ParseOptions::default());
+        }
+    }
     
-    info!("Adblock engine initialized with {} total rules", total_rules);
-    Engine::from_filter_set(filter_set, true)
+    info!("Adblock engines initialized with {} total rules", total_rules);
+    filter_sets.into_iter()
+        .map(|(category, filter_set)| (category, Engine::from_filter_set(filter_set, true)))
+        .collect()
+}
+
+/// Whether a filter rule is an exception (network `@@`, cosmetic `#@#` and variants)
+fn is_exception(rule: &str) -> bool {
+    let rule = rule.trim_start();
+    rule.starts_with("@@") || (!rule.starts_with('!') && rule.contains("#@"))
 }
 
 /// Download a filter list
@@ -107,54 +324,79 @@ fn download_filter_list(url: &str) -> Result<String, String> {
 
 /// Check if a URL should be blocked
 pub fn should_block(url: &str, source_url: &str, request_type: &str) -> bool {
-    ADBLOCK_ENGINE.with(|engine| {
-        let engine = engine.borrow();
-        let Some(engine) = engine.as_ref() else {
-            return false; // Engine not ready yet
-        };
-        
-        match adblock::request::Request::new(url, source_url, request_type) {
-            Ok(request) => engine.check_network_request(&request).matched,
-            Err(_) => false,
-        }
+    check(url, source_url, request_type).is_some()
+}
+
+/// Category of the first enabled engine that blocks a URL
+pub fn check(url: &str, source_url: &str, request_type: &str) -> Option<TrackerCategory> {
+    if !crate::config::with_config(|c| c.adblock.enabled) {
+        return None;
+    }
+    if with_blocking_prefs(|p| p.is_site_allowed(source_url)) {
+        return None;
+    }
+    let Ok(request) = adblock::request::Request::new(url, source_url, request_type) else {
+        return None;
+    };
+    ADBLOCK_ENGINES.with(|engines| {
+        // Empty until the engines are ready
+        engines.borrow().iter()
+            .filter(|(category, _)| with_blocking_prefs(|p| p.is_enabled(*category)))
+            .find(|(_, engine)| engine.check_network_request(&request).matched)
+            .map(|(category, _)| *category)
     })
 }
 
-/// Get cosmetic filters (CSS rules to hide elements) for a URL
-pub fn get_cosmetic_filters(url: &str) -> String {
-    ADBLOCK_ENGINE.with(|engine| {
-        let engine = engine.borrow();
-        let Some(engine) = engine.as_ref() else {
-            return String::new();
-        };
-        
-        let resources = engine.url_cosmetic_resources(url);
-        
-        // Build CSS to hide elements
-        let mut css = String::new();
-        
-        // Hide matched selectors
-        for selector in &resources.hide_selectors {
-            if !css.is_empty() {
-                css.push(',');
+/// Get cosmetic filters (CSS rules to hide elements) for a URL,
+/// with the number of hiding selectors they contain
+pub fn get_cosmetic_filters(url: &str) -> (String, usize) {
+    if with_blocking_prefs(|p| p.is_site_allowed(url)) {
+        return (String::new(), 0);
+    }
+    ADBLOCK_ENGINES.with(|engines| {
+        let engines = engines.borrow();
+        let mut selectors: Vec<String> = Vec::new();
+        let mut injected = String::new();
+
+        for (category, engine) in engines.iter() {
+            if !with_blocking_prefs(|p| p.is_enabled(*category)) {
+                continue;
+            }
+            let resources = engine.url_cosmetic_resources(url);
+            selectors.extend(resources.hide_selectors);
+            if !resources.injected_script.is_empty() {
+                injected.push('\n');
+                injected.push_str(&resources.injected_script);
             }
-            css.push_str(selector);
         }
+        selectors.sort();
+        selectors.dedup();
+        let hidden = with_blocking_prefs(|p| p.hidden_selectors(url));
+        let count = selectors.len() + hidden.len();
+        
+        // Build CSS to hide matched selectors
+        let mut css = selectors.join(",");
         
         if !css.is_empty() {
             css.push_str(" { display: none !important; visibility: hidden !important; }");
         }
         
-        // Add injected CSS
-        if !resources.injected_script.is_empty() {
-            css.push_str("\n");
-            css.push_str(&resources.injected_script);
+        // User rules get a CSS rule each, so one bad selector cannot void the rest
+        for selector in hidden {
+            css.push_str(&format!("\n{} {{ display: none !important; }}", selector));
         }
+
+        // Add injected CSS
+        css.push_str(&injected);
         
-        css
+        (css, count)
     })
 }
 
+/// Script behind the context menu's "Block Element": hides the element last
+/// right-clicked and returns a CSS selector for it
+pub const BLOCK_ELEMENT_SCRIPT: &str = include_str!("pages/block-element.js");
+
 /// Get YouTube ad-skip script
 /// This script auto-skips YouTube ads and removes ad overlays
 pub fn get_youtube_adskip_script() -> &'static str {
@@ -245,22 +487,25 @@ pub fn get_cosmetic_script(css: &str) -> String {
         return String::new();
     }
     
+    // A JSON string is a JS string literal: backslashes in selectors
+    // (`.md\:hidden`) survive, and page-chosen ids cannot end the literal
+    let css = serde_json::to_string(css).unwrap_or_default();
     format!(r#"
     (function() {{
         'use strict';
         const style = document.createElement('style');
-        style.textContent = `{}`;
+        style.textContent = {};
         document.head.appendChild(style);
     }})();
-    "#, css.replace('`', "\\`").replace("${", "\\${"))
+    "#, css)
 }
 
 /// Initialize the adblocker (call at startup on main thread)
 pub fn init() {
     info!("Initializing enhanced adblocker...");
-    let engine = create_engine();
-    ADBLOCK_ENGINE.with(|e| {
-        *e.borrow_mut() = Some(engine);
+    let engines = create_engines();
+    ADBLOCK_ENGINES.with(|e| {
+        *e.borrow_mut() = engines;
     });
     info!("Enhanced adblocker ready");
 }
@@ -272,15 +517,46 @@ pub fn refresh_filters() {
     let filter_dir = get_filter_dir();
     
     // Delete cached filters
-    for (name, _) in FILTER_LISTS {
+    for (name, _, _) in FILTER_LISTS {
         let cache_path = filter_dir.join(format!("{}.txt", name));
         fs::remove_file(&cache_path).ok();
     }
     
-    // Recreate engine
-    let engine = create_engine();
-    ADBLOCK_ENGINE.with(|e| {
-        *e.borrow_mut() = Some(engine);
+    // Recreate engines
+    let engines = create_engines();
+    ADBLOCK_ENGINES.with(|e| {
+        *e.borrow_mut() = engines;
     });
     info!("Filter lists refreshed");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The CSS string the cosmetic script assigns, decoded
+    fn assigned_css(script: &str) -> String {
+        let literal = script.split("style.textContent = ").nth(1)
+            .and_then(|rest| rest.split(";\n").next())
+            .expect("script assigns textContent");
+        serde_json::from_str(literal).expect("textContent is a string literal")
+    }
+
+    #[test]
+    fn cosmetic_script_keeps_escaped_selectors() {
+        // As block-element.js stores them, after CSS.escape
+        let css = ".md\\:hidden { display: none !important; }";
+        assert_eq!(assigned_css(&get_cosmetic_script(css)), css);
+    }
+
+    #[test]
+    fn cosmetic_script_cannot_be_escaped_by_selectors() {
+        let css = "#x\\`\\;alert\\(1\\)\\;\\/\\/ { display: none !important; }\n.a\\${b} { display: none !important; }";
+        assert_eq!(assigned_css(&get_cosmetic_script(css)), css);
+    }
+
+    #[test]
+    fn empty_css_gives_no_script() {
+        assert!(get_cosmetic_script("").is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! Archive Module - Export/import of all browser data
+//!
+//! Packs the stores and settings from the data directory into one versioned
+//! archive file, so a profile can be moved to another machine without
+//! any cloud sync. Import can restore every section or only selected ones.
+//!
+//! Cookies are not included: they live in WebKit's SQLite database and
+//! would be stored unencrypted in the archive. Neither are installed
+//! extensions (the `extensions/` folder holds their code; copy it as is),
+//! only which ones are off and their storage.local.
+//!
+//! A running browser keeps its stores in memory and would write them back
+//! over imported files, so import refuses to run while one is open.
+
+use anyhow::{bail, Context, Result};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::history::now_secs;
+
+/// Identifies fOS-WB archive files
+const ARCHIVE_FORMAT: &str = "fos-wb-archive";
+
+/// Current archive schema version
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Archive sections and the data directory file each one holds
+/// (a trailing `/` is a folder of JSON files, archived by file name).
+/// Fingerprinting, user agent and shortcut settings are in config.toml;
+/// blocked elements and sites with blocking off are in blocking.json.
+pub const SECTIONS: &[(&str, &str)] = &[
+    ("bookmarks", "bookmarks.json"),
+    ("history", "history.json"),
+    ("session", "session.json"),
+    ("zoom", "zoom.json"),
+    ("blocking", "blocking.json"),
+    ("blockstats", "blockstats.json"),
+    ("styles", "styles.json"),
+    ("settings", "config.toml"),
+    ("search", "search_engines.json"),
+    ("extensions", "extensions.json"),
+    ("extension_storage", "extension_storage/"),
+];
+
+/// On-disk archive layout
+#[derive(Serialize, Deserialize)]
+struct Archive {
+    format: String,
+    version: u32,
+    /// Unix timestamp (seconds)
+    created: u64,
+    sections: BTreeMap<String, serde_json::Value>,
+}
+
+/// Write every available section to an archive file, returning their names
+pub fn export_archive(path: &Path) -> Result<Vec<&'static str>> {
+    let data_dir = crate::webview::get_data_dir();
+    let mut archive = Archive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        created: now_secs(),
+        sections: BTreeMap::new(),
+    };
+
+    let mut exported = Vec::new();
+    for (section, file) in SECTIONS {
+        let Some(value) = read_section(&data_dir, file) else {
+            continue; // Nothing saved yet
+        };
+        match value {
+            Ok(value) => {
+                archive.sections.insert(section.to_string(), value);
+                exported.push(*section);
+            }
+            Err(e) => warn!("Skipping unreadable {}: {}", file, e),
+        }
+    }
+
+    fs::write(path, serde_json::to_string_pretty(&archive)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    info!("Exported {} sections to {:?}", exported.len(), path);
+    Ok(exported)
+}
+
+/// Restore sections from an archive file (all of them if `only` is empty),
+/// returning the names of the restored sections
+pub fn import_archive(path: &Path, only: &[String]) -> Result<Vec<&'static str>> {
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let archive: Archive = serde_json::from_str(&data)
+        .with_context(|| format!("{} is not a fOS-WB archive", path.display()))?;
+
+    if archive.format != ARCHIVE_FORMAT {
+        bail!("{} is not a fOS-WB archive", path.display());
+    }
+    if archive.version > ARCHIVE_VERSION {
+        bail!("Archive version {} is newer than this browser supports ({})", archive.version, ARCHIVE_VERSION);
+    }
+    if let Some(unknown) = only.iter().find(|name| !SECTIONS.iter().any(|(s, _)| s == name)) {
+        bail!("Unknown section: {} (expected one of {})", unknown, section_names());
+    }
+    crate::webview::ensure_not_running()?;
+
+    let data_dir = crate::webview::get_data_dir();
+    let mut restored = Vec::new();
+    for (section, file) in SECTIONS {
+        if !only.is_empty() && !only.iter().any(|name| name == section) {
+            continue;
+        }
+        let Some(value) = archive.sections.get(*section) else {
+            continue;
+        };
+        write_section(&data_dir, file, value)?;
+        restored.push(*section);
+    }
+
+    info!("Restored {} sections from {:?}", restored.len(), path);
+    Ok(restored)
+}
+
+/// Read a section's file or folder as a JSON value (None if nothing is saved)
+fn read_section(data_dir: &Path, file: &str) -> Option<Result<serde_json::Value>> {
+    let Some(folder) = file.strip_suffix('/') else {
+        let data = fs::read_to_string(data_dir.join(file)).ok()?;
+        return Some(parse_section(file, &data));
+    };
+
+    let mut files = serde_json::Map::new();
+    for entry in fs::read_dir(data_dir.join(folder)).ok()?.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let value = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(serde_json::from_str(&data)?));
+        match value {
+            Ok(value) => {
+                files.insert(name.to_string(), value);
+            }
+            Err(e) => warn!("Skipping unreadable {:?}: {}", path, e),
+        }
+    }
+    (!files.is_empty()).then(|| Ok(serde_json::Value::Object(files)))
+}
+
+/// Write a section back to its file, or to the files of its folder
+fn write_section(data_dir: &Path, file: &str, value: &serde_json::Value) -> Result<()> {
+    let Some(folder) = file.strip_suffix('/') else {
+        fs::write(data_dir.join(file), format_section(file, value)?)?;
+        return Ok(());
+    };
+
+    let Some(files) = value.as_object() else {
+        bail!("Section {} is not a set of files", folder);
+    };
+    let dir = data_dir.join(folder);
+    fs::create_dir_all(&dir)?;
+    for (name, value) in files {
+        // Names come from the archive: no paths
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            warn!("Skipping {}/{} from the archive", folder, name);
+            continue;
+        }
+        fs::write(dir.join(format!("{}.json", name)), serde_json::to_string(value)?)?;
+    }
+    Ok(())
+}
+
+/// Read a data file (JSON, or TOML for settings) as a JSON value
+fn parse_section(file: &str, data: &str) -> Result<serde_json::Value> {
+    if file.ends_with(".toml") {
+        Ok(toml::from_str(data)?)
+    } else {
+        Ok(serde_json::from_str(data)?)
+    }
+}
+
+/// Write a JSON value back in the data file's own format
+fn format_section(file: &str, value: &serde_json::Value) -> Result<String> {
+    if file.ends_with(".toml") {
+        Ok(toml::to_string_pretty(value)?)
+    } else {
+        Ok(serde_json::to_string_pretty(value)?)
+    }
+}
+
+/// Comma-separated list of section names
+pub fn section_names() -> String {
+    SECTIONS.iter().map(|(s, _)| *s).collect::<Vec<_>>().join(", ")
+}
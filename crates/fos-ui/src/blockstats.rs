@@ -0,0 +1,237 @@
+//! Block Statistics Module
+//!
+//! Features:
+//! - Counts blocked requests per day, blocked domain, first-party site, and tracker category
+//! - Estimates bandwidth saved from response sizes
+//! - Persisted as compact JSON in the data directory (last 90 days)
+//! - Weekly report for fos://stats and the `--stats` command
+//!
+//! Everything is computed and stored locally.
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use crate::adblocker::TrackerCategory;
+use crate::history::now_secs;
+use crate::omnibox::host_of;
+
+/// Days of statistics kept on disk
+const RETENTION_DAYS: usize = 90;
+
+/// Size assumed for blocked responses without a Content-Length
+const ESTIMATED_BLOCKED_BYTES: u64 = 16 * 1024;
+
+/// Statistics for one day
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct DayStats {
+    pub blocked: u64,
+    pub bytes_saved: u64,
+    /// Blocked domain -> count
+    pub domains: HashMap<String, u64>,
+    /// First-party site -> count
+    pub sites: HashMap<String, u64>,
+    /// Tracker category -> count
+    #[serde(default)]
+    pub categories: HashMap<String, u64>,
+}
+
+/// Aggregated block statistics keyed by day (`YYYY-MM-DD`)
+#[derive(Serialize, Deserialize, Default)]
+pub struct BlockStats {
+    days: BTreeMap<String, DayStats>,
+    /// Changed since the last save
+    #[serde(skip)]
+    dirty: bool,
+}
+
+/// Summary over a range of days
+#[derive(Default)]
+pub struct Report {
+    pub days: usize,
+    pub blocked: u64,
+    pub bytes_saved: u64,
+    pub top_domains: Vec<(String, u64)>,
+    pub top_sites: Vec<(String, u64)>,
+    /// (category, blocked) for every category, in display order
+    pub per_category: Vec<(TrackerCategory, u64)>,
+    /// (day, blocked) for every day in the range, oldest first
+    pub per_day: Vec<(String, u64)>,
+}
+
+// Thread-local stats (since we're running single-threaded GTK)
+thread_local! {
+    static STATS: RefCell<BlockStats> = RefCell::new(BlockStats::load());
+}
+
+/// Run a closure with the block statistics
+pub fn with_stats<R>(f: impl FnOnce(&mut BlockStats) -> R) -> R {
+    STATS.with(|stats| f(&mut stats.borrow_mut()))
+}
+
+/// Record a blocked request
+pub fn record_block(url: &str, source_url: &str, category: TrackerCategory, content_length: u64) {
+    with_stats(|stats| stats.record(url, source_url, category, content_length, now_secs()));
+}
+
+impl BlockStats {
+    /// Load statistics from the data directory
+    pub fn load() -> Self {
+        let path = crate::webview::get_data_dir().join("blockstats.json");
+        if let Ok(data) = fs::read_to_string(&path) {
+            serde_json::from_str(&data).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Save statistics to the data directory, dropping expired days
+    pub fn save(&mut self) {
+        while self.days.len() > RETENTION_DAYS {
+            self.days.pop_first();
+        }
+        let path = crate::webview::get_data_dir().join("blockstats.json");
+        if let Ok(json) = serde_json::to_string(self) {
+            fs::write(path, json).ok();
+        }
+        self.dirty = false;
+    }
+
+    /// Save statistics if they changed since the last save
+    pub fn save_if_dirty(&mut self) {
+        if self.dirty {
+            self.save();
+        }
+    }
+
+    /// Record a blocked request at a given time
+    pub fn record(&mut self, url: &str, source_url: &str, category: TrackerCategory, content_length: u64, now: u64) {
+        self.dirty = true;
+        let day = self.days.entry(day_key(now)).or_default();
+        day.blocked += 1;
+        day.bytes_saved += if content_length > 0 { content_length } else { ESTIMATED_BLOCKED_BYTES };
+        *day.domains.entry(host_of(url).to_string()).or_default() += 1;
+        *day.categories.entry(category.name().to_string()).or_default() += 1;
+
+        let site = host_of(source_url);
+        if !site.is_empty() {
+            *day.sites.entry(site.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Summarise the last `days` days (including today)
+    pub fn report(&self, days: usize, now: u64) -> Report {
+        let mut report = Report { days, ..Default::default() };
+        let mut domains: HashMap<&str, u64> = HashMap::new();
+        let mut sites: HashMap<&str, u64> = HashMap::new();
+        let mut categories: HashMap<&str, u64> = HashMap::new();
+
+        for offset in (0..days as u64).rev() {
+            let key = day_key(now.saturating_sub(offset * 86_400));
+            let blocked = match self.days.get(&key) {
+                Some(day) => {
+                    report.blocked += day.blocked;
+                    report.bytes_saved += day.bytes_saved;
+                    for (domain, count) in &day.domains {
+                        *domains.entry(domain).or_default() += count;
+                    }
+                    for (site, count) in &day.sites {
+                        *sites.entry(site).or_default() += count;
+                    }
+                    for (category, count) in &day.categories {
+                        *categories.entry(category).or_default() += count;
+                    }
+                    day.blocked
+                }
+                None => 0,
+            };
+            report.per_day.push((key, blocked));
+        }
+
+        report.top_domains = top_n(domains, 10);
+        report.top_sites = top_n(sites, 10);
+        report.per_category = TrackerCategory::ALL.into_iter()
+            .map(|c| (c, categories.get(c.name()).copied().unwrap_or(0)))
+            .collect();
+        report
+    }
+}
+
+impl Report {
+    /// Plain-text rendering (used by the `--stats` command)
+    pub fn to_text(&self) -> String {
+        let mut out = format!(
+            "fOS-WB blocking report (last {} days)\n\
+             Requests blocked:         {}\n\
+             Estimated data saved:     {}\n",
+            self.days, self.blocked, format_bytes(self.bytes_saved)
+        );
+
+        out.push_str("\nPer day:\n");
+        for (day, blocked) in &self.per_day {
+            out.push_str(&format!("  {}  {}\n", day, blocked));
+        }
+
+        out.push_str("\nBy category:\n");
+        for (category, blocked) in &self.per_category {
+            out.push_str(&format!("  {:>7}  {}\n", blocked, category.label()));
+        }
+
+        out.push_str("\nTop blocked domains:\n");
+        for (domain, count) in &self.top_domains {
+            out.push_str(&format!("  {:>7}  {}\n", count, domain));
+        }
+
+        out.push_str("\nTop offending sites:\n");
+        for (site, count) in &self.top_sites {
+            out.push_str(&format!("  {:>7}  {}\n", count, site));
+        }
+        out
+    }
+}
+
+fn top_n(counts: HashMap<&str, u64>, n: usize) -> Vec<(String, u64)> {
+    let mut sorted: Vec<(String, u64)> = counts.into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted.truncate(n);
+    sorted
+}
+
+/// Human-readable byte count
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Calendar day (UTC) of a Unix timestamp as `YYYY-MM-DD`
+fn day_key(secs: u64) -> String {
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Weekly report from the statistics on disk
+pub fn weekly_report() -> Report {
+    BlockStats::load().report(7, now_secs())
+}
@@ -0,0 +1,331 @@
+//! Bookmarks Module
+//!
+//! Features:
+//! - Bookmarks organised in a folder hierarchy
+//! - Persisted as JSON in the data directory
+//! - Keyword search over titles and URLs
+//! - Netscape bookmark file (HTML) import/export
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::history::now_secs;
+use crate::protocol::escape_html;
+
+/// A single bookmarked page
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bookmark {
+    pub id: u64,
+    /// Parent folder, `None` for the root
+    pub folder: Option<u64>,
+    pub title: String,
+    pub url: String,
+    /// Unix timestamp (seconds)
+    pub added: u64,
+}
+
+/// A bookmark folder
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Folder {
+    pub id: u64,
+    /// Parent folder, `None` for the root
+    pub parent: Option<u64>,
+    pub name: String,
+    pub added: u64,
+}
+
+/// Bookmark storage with folder hierarchy
+#[derive(Serialize, Deserialize, Default)]
+pub struct BookmarkStore {
+    next_id: u64,
+    folders: Vec<Folder>,
+    bookmarks: Vec<Bookmark>,
+}
+
+// Thread-local store (since we're running single-threaded GTK)
+thread_local! {
+    static BOOKMARKS: RefCell<BookmarkStore> = RefCell::new(BookmarkStore::load());
+}
+
+/// Run a closure with the bookmark store
+pub fn with_bookmarks<R>(f: impl FnOnce(&mut BookmarkStore) -> R) -> R {
+    BOOKMARKS.with(|store| f(&mut store.borrow_mut()))
+}
+
+impl BookmarkStore {
+    /// Load bookmarks from the data directory
+    pub fn load() -> Self {
+        let path = crate::webview::get_data_dir().join("bookmarks.json");
+        if let Ok(data) = fs::read_to_string(&path) {
+            serde_json::from_str(&data).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Save bookmarks to the data directory
+    pub fn save(&self) {
+        let path = crate::webview::get_data_dir().join("bookmarks.json");
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            fs::write(path, json).ok();
+        }
+    }
+
+    fn alloc_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Add a bookmark and return its id
+    pub fn add(&mut self, url: &str, title: &str, folder: Option<u64>) -> u64 {
+        let id = self.alloc_id();
+        self.bookmarks.push(Bookmark {
+            id,
+            folder,
+            title: title.to_string(),
+            url: url.to_string(),
+            added: now_secs(),
+        });
+        id
+    }
+
+    /// Create a folder and return its id
+    pub fn create_folder(&mut self, name: &str, parent: Option<u64>) -> u64 {
+        let id = self.alloc_id();
+        self.folders.push(Folder {
+            id,
+            parent,
+            name: name.to_string(),
+            added: now_secs(),
+        });
+        id
+    }
+
+    /// Remove a bookmark, returning it if it existed
+    pub fn remove(&mut self, id: u64) -> Option<Bookmark> {
+        let pos = self.bookmarks.iter().position(|b| b.id == id)?;
+        Some(self.bookmarks.remove(pos))
+    }
+
+    /// Remove a folder together with everything inside it,
+    /// returning the removed folders and bookmarks
+    pub fn remove_folder(&mut self, id: u64) -> (Vec<Folder>, Vec<Bookmark>) {
+        let mut removed = (Vec::new(), Vec::new());
+        let children: Vec<u64> = self.folders.iter()
+            .filter(|f| f.parent == Some(id))
+            .map(|f| f.id)
+            .collect();
+        for child in children {
+            let (folders, bookmarks) = self.remove_folder(child);
+            removed.0.extend(folders);
+            removed.1.extend(bookmarks);
+        }
+
+        let (gone, kept): (Vec<Bookmark>, Vec<Bookmark>) = std::mem::take(&mut self.bookmarks)
+            .into_iter()
+            .partition(|b| b.folder == Some(id));
+        self.bookmarks = kept;
+        removed.1.extend(gone);
+        if let Some(pos) = self.folders.iter().position(|f| f.id == id) {
+            removed.0.push(self.folders.remove(pos));
+        }
+        removed
+    }
+
+    /// Put back removed folders and bookmarks (used by undo)
+    pub fn restore(&mut self, folders: Vec<Folder>, bookmarks: Vec<Bookmark>) {
+        for folder in folders {
+            if !self.folders.iter().any(|f| f.id == folder.id) {
+                self.folders.push(folder);
+            }
+        }
+        for bookmark in bookmarks {
+            if !self.bookmarks.iter().any(|b| b.id == bookmark.id) {
+                self.bookmarks.push(bookmark);
+            }
+        }
+    }
+
+    /// Move a bookmark into another folder
+    pub fn move_to(&mut self, id: u64, folder: Option<u64>) {
+        if folder.is_some_and(|f| !self.folders.iter().any(|x| x.id == f)) {
+            return;
+        }
+        if let Some(b) = self.bookmarks.iter_mut().find(|b| b.id == id) {
+            b.folder = folder;
+        }
+    }
+
+    /// Find the bookmark for a URL
+    pub fn find_by_url(&self, url: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|b| b.url == url)
+    }
+
+    /// Check whether a URL is bookmarked
+    pub fn is_bookmarked(&self, url: &str) -> bool {
+        self.find_by_url(url).is_some()
+    }
+
+    /// All bookmarks, in insertion order
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    /// All folders, in creation order
+    pub fn all_folders(&self) -> &[Folder] {
+        &self.folders
+    }
+
+    /// Folders directly inside `parent`
+    pub fn folders_in(&self, parent: Option<u64>) -> Vec<&Folder> {
+        self.folders.iter().filter(|f| f.parent == parent).collect()
+    }
+
+    /// Bookmarks directly inside `folder`
+    pub fn bookmarks_in(&self, folder: Option<u64>) -> Vec<&Bookmark> {
+        self.bookmarks.iter().filter(|b| b.folder == folder).collect()
+    }
+
+    /// Keyword search: every word must appear in the title or URL
+    pub fn search(&self, query: &str) -> Vec<&Bookmark> {
+        let words: Vec<String> = query.split_whitespace()
+            .map(|w| w.to_lowercase())
+            .collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        self.bookmarks.iter().filter(|b| {
+            let title = b.title.to_lowercase();
+            let url = b.url.to_lowercase();
+            words.iter().all(|w| title.contains(w) || url.contains(w))
+        }).collect()
+    }
+
+    /// Export as a Netscape bookmark file (the format every browser imports)
+    pub fn export_html(&self) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+             <TITLE>Bookmarks</TITLE>\n\
+             <H1>Bookmarks</H1>\n"
+        );
+        self.export_folder(None, 0, &mut out);
+        out
+    }
+
+    fn export_folder(&self, folder: Option<u64>, depth: usize, out: &mut String) {
+        let indent = "    ".repeat(depth);
+        out.push_str(&format!("{}<DL><p>\n", indent));
+
+        for f in self.folders_in(folder) {
+            out.push_str(&format!(
+                "{}    <DT><H3 ADD_DATE=\"{}\">{}</H3>\n",
+                indent, f.added, escape_html(&f.name)
+            ));
+            self.export_folder(Some(f.id), depth + 1, out);
+        }
+
+        for b in self.bookmarks_in(folder) {
+            out.push_str(&format!(
+                "{}    <DT><A HREF=\"{}\" ADD_DATE=\"{}\">{}</A>\n",
+                indent, escape_html(&b.url), b.added, escape_html(&b.title)
+            ));
+        }
+
+        out.push_str(&format!("{}</DL><p>\n", indent));
+    }
+
+    /// Import a Netscape bookmark file, returning the number of bookmarks added
+    pub fn import_html(&mut self, html: &str) -> usize {
+        let lower = html.to_ascii_lowercase();
+        let mut stack: Vec<Option<u64>> = Vec::new();
+        let mut pending_folder: Option<u64> = None;
+        let mut imported = 0;
+        let mut pos = 0;
+
+        while let Some(offset) = lower[pos..].find('<') {
+            let start = pos + offset;
+            let rest = &lower[start..];
+            let current = stack.last().copied().flatten();
+
+            if rest.starts_with("<dl") {
+                // A list opens either the folder just declared or the root
+                stack.push(pending_folder.take().or(current));
+            } else if rest.starts_with("</dl") {
+                stack.pop();
+            } else if rest.starts_with("<h3") {
+                if let Some((name, end)) = tag_text(html, &lower, start, "</h3") {
+                    pending_folder = Some(self.create_folder(&name, current));
+                    pos = end;
+                    continue;
+                }
+            } else if rest.starts_with("<a ") {
+                let tag_end = rest.find('>').map(|e| start + e).unwrap_or(lower.len());
+                let href = attr_value(&html[start..tag_end], &lower[start..tag_end], "href");
+                if let Some((title, end)) = tag_text(html, &lower, start, "</a") {
+                    if let Some(url) = href {
+                        let title = if title.is_empty() { url.clone() } else { title };
+                        self.add(&url, &title, current);
+                        imported += 1;
+                    }
+                    pos = end;
+                    continue;
+                }
+            }
+
+            pos = start + 1;
+        }
+
+        info!("Imported {} bookmarks", imported);
+        imported
+    }
+}
+
+/// Text between the end of the tag starting at `start` and `close`,
+/// plus the position right after the closing tag
+fn tag_text(html: &str, lower: &str, start: usize, close: &str) -> Option<(String, usize)> {
+    let open_end = start + lower[start..].find('>')? + 1;
+    let close_start = open_end + lower[open_end..].find(close)?;
+    let text = unescape_html(html[open_end..close_start].trim());
+    Some((text, close_start + close.len()))
+}
+
+/// Value of a quoted attribute inside a tag
+fn attr_value(tag: &str, lower_tag: &str, name: &str) -> Option<String> {
+    let key = format!("{}=\"", name);
+    let start = lower_tag.find(&key)? + key.len();
+    let end = start + tag[start..].find('"')?;
+    Some(unescape_html(&tag[start..end]))
+}
+
+fn unescape_html(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Import bookmarks from a Netscape HTML file on disk (refused while the
+/// browser runs, as it would save its own bookmarks over them)
+pub fn import_file(path: &Path) -> anyhow::Result<usize> {
+    crate::webview::ensure_not_running()?;
+    let html = fs::read_to_string(path)?;
+    Ok(with_bookmarks(|store| {
+        let count = store.import_html(&html);
+        store.save();
+        count
+    }))
+}
+
+/// Export bookmarks to a Netscape HTML file on disk
+pub fn export_file(path: &Path) -> anyhow::Result<()> {
+    let html = with_bookmarks(|store| store.export_html());
+    fs::write(path, html)?;
+    info!("Exported bookmarks to {:?}", path);
+    Ok(())
+}
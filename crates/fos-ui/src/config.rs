@@ -0,0 +1,151 @@
+//! Config Module - Runtime settings
+//!
+//! Features:
+//! - Settings kept as TOML in the data directory (`config.toml`)
+//! - The file is watched, so hand edits apply without a restart
+//! - Edited on the fos://settings page
+//! - Keyboard shortcuts can be rebound in the `[keys]` table
+//!
+//! Missing keys fall back to their defaults, so old files keep working.
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{info, warn};
+use webkit6::gio::{self, prelude::*};
+
+use crate::fingerprint::FingerprintConfig;
+use crate::keymap::KeymapConfig;
+use crate::user_agent::UserAgentConfig;
+
+/// Adblocker switches
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AdblockConfig {
+    /// Block network requests matched by the filter lists
+    pub enabled: bool,
+    /// Hide page elements matched by cosmetic filters
+    pub cosmetic_filters: bool,
+    /// Skip and mute YouTube ads
+    pub youtube_adskip: bool,
+}
+
+impl Default for AdblockConfig {
+    fn default() -> Self {
+        Self { enabled: true, cosmetic_filters: true, youtube_adskip: true }
+    }
+}
+
+/// Browser settings
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Page opened in new tabs
+    pub homepage: String,
+    /// Id of the default search engine (see the search module)
+    pub search_engine: String,
+    /// GPU compositing (off by default: it flickers on some GPUs)
+    pub hardware_acceleration: bool,
+    /// Resolve hosts of hovered links and predicted addresses ahead of time
+    pub dns_prefetch: bool,
+    pub adblock: AdblockConfig,
+    pub fingerprint: FingerprintConfig,
+    pub user_agent: UserAgentConfig,
+    /// Keyboard shortcut overrides (`[keys]` table)
+    pub keys: KeymapConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            homepage: "https://duckduckgo.com".to_string(),
+            search_engine: "duckduckgo".to_string(),
+            hardware_acceleration: false,
+            dns_prefetch: true,
+            adblock: AdblockConfig::default(),
+            fingerprint: FingerprintConfig::default(),
+            user_agent: UserAgentConfig::default(),
+            keys: KeymapConfig::default(),
+        }
+    }
+}
+
+/// Keeps the file monitor alive for the lifetime of the thread
+struct Watch {
+    _monitor: gio::FileMonitor,
+}
+
+// Thread-local config (since we're running single-threaded GTK)
+thread_local! {
+    static CONFIG: RefCell<Config> = RefCell::new(Config::load());
+    static WATCH: RefCell<Option<Watch>> = const { RefCell::new(None) };
+}
+
+/// Run a closure with the settings
+pub fn with_config<R>(f: impl FnOnce(&mut Config) -> R) -> R {
+    CONFIG.with(|config| f(&mut config.borrow_mut()))
+}
+
+fn config_path() -> PathBuf {
+    crate::webview::get_data_dir().join("config.toml")
+}
+
+impl Config {
+    /// Load settings from the data directory
+    pub fn load() -> Self {
+        let Ok(data) = fs::read_to_string(config_path()) else {
+            return Self::default();
+        };
+        let config: Self = toml::from_str(&data).unwrap_or_else(|e| {
+            warn!("Ignoring invalid config.toml: {}", e);
+            Self::default()
+        });
+        for problem in config.keys.problems() {
+            warn!("config.toml [keys]: {}", problem);
+        }
+        config
+    }
+
+    /// Save settings to the data directory
+    pub fn save(&self) {
+        if let Ok(text) = toml::to_string_pretty(self) {
+            fs::write(config_path(), text).ok();
+        }
+    }
+
+}
+
+/// Percent-encode a search query (spaces become `+`)
+pub(crate) fn encode_query(query: &str) -> String {
+    let mut out = String::with_capacity(query.len());
+    for b in query.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Reload settings whenever config.toml changes on disk, then call `on_change`
+pub(crate) fn watch(on_change: impl Fn() + 'static) {
+    let file = gio::File::for_path(config_path());
+    let monitor = match file.monitor_file(gio::FileMonitorFlags::NONE, None::<&gio::Cancellable>) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            warn!("Cannot watch config.toml: {}", e);
+            return;
+        }
+    };
+
+    monitor.connect_changed(move |_, _, _, event| {
+        if matches!(event, gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created) {
+            with_config(|config| *config = Config::load());
+            info!("Reloaded config.toml");
+            on_change();
+        }
+    });
+    WATCH.with(|watch| *watch.borrow_mut() = Some(Watch { _monitor: monitor }));
+}
@@ -0,0 +1,405 @@
+//! Extensions Module - Content-script extensions
+//!
+//! A small subset of WebExtensions, enough for simple community scripts:
+//! each folder in `extensions/` (in the data directory) holding a
+//! `manifest.json` is an extension. Its `content_scripts` (`matches`, `js`,
+//! `css`, `run_at`, `all_frames`) are installed through WebKit's user
+//! content manager, the scripts in a JavaScript world of their own.
+//!
+//! Scripts get `browser`/`chrome` with `runtime.id` and `storage.local`,
+//! which is kept as JSON in `extension_storage/<id>.json`. There are no
+//! background pages, permissions or other APIs. Extensions are listed and
+//! switched on or off at fos://extensions. Private tabs use a content
+//! manager without extensions, so nothing they do is written to disk.
+
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::{info, warn};
+use webkit6::javascriptcore;
+use webkit6::{
+    UserContentInjectedFrames, UserContentManager, UserScript, UserScriptInjectionTime,
+    UserStyleLevel, UserStyleSheet,
+};
+
+/// The extension API shim, with `__FOS_HANDLER__` and `__FOS_ID__` to be replaced
+const API_SCRIPT: &str = include_str!("pages/extension-api.js");
+
+/// Largest storage.local an extension may keep
+pub const MAX_STORAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// The parts of manifest.json we read
+#[derive(Deserialize, Clone)]
+pub struct Manifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub content_scripts: Vec<ContentScript>,
+}
+
+/// One `content_scripts` entry
+#[derive(Deserialize, Clone)]
+pub struct ContentScript {
+    pub matches: Vec<String>,
+    #[serde(default)]
+    pub exclude_matches: Vec<String>,
+    #[serde(default)]
+    pub js: Vec<String>,
+    #[serde(default)]
+    pub css: Vec<String>,
+    /// `document_start`, `document_end` or `document_idle` (the default)
+    #[serde(default)]
+    pub run_at: String,
+    #[serde(default)]
+    pub all_frames: bool,
+}
+
+/// An extension found in the extensions folder
+#[derive(Clone)]
+pub struct Extension {
+    /// Folder name
+    pub id: String,
+    pub dir: PathBuf,
+    pub manifest: Manifest,
+    pub enabled: bool,
+}
+
+/// Extensions the user switched off, persisted as extensions.json
+#[derive(Serialize, Deserialize, Default)]
+struct ExtensionPrefs {
+    disabled: Vec<String>,
+}
+
+/// Installed extensions and the folders that could not be loaded
+#[derive(Default)]
+pub struct ExtensionStore {
+    extensions: Vec<Extension>,
+    /// (folder, reason)
+    errors: Vec<(String, String)>,
+    prefs: ExtensionPrefs,
+}
+
+/// What is installed in the shared content manager
+#[derive(Default)]
+struct Installed {
+    manager: Option<UserContentManager>,
+    scripts: Vec<UserScript>,
+    sheets: Vec<UserStyleSheet>,
+    /// (handler name, world) registered for storage messages
+    handlers: Vec<(String, String)>,
+    /// Handler names with a connected signal (kept across refreshes)
+    connected: HashSet<String>,
+}
+
+// Thread-local store (since we're running single-threaded GTK)
+thread_local! {
+    static STORE: RefCell<ExtensionStore> = RefCell::new(ExtensionStore::load());
+    static INSTALLED: RefCell<Installed> = RefCell::new(Installed::default());
+}
+
+/// Run a closure with the extension store
+pub fn with_extensions<R>(f: impl FnOnce(&mut ExtensionStore) -> R) -> R {
+    STORE.with(|store| f(&mut store.borrow_mut()))
+}
+
+/// Folder extensions are installed in
+pub fn extensions_dir() -> PathBuf {
+    let dir = crate::webview::get_data_dir().join("extensions");
+    fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn prefs_path() -> PathBuf {
+    crate::webview::get_data_dir().join("extensions.json")
+}
+
+fn storage_path(id: &str) -> PathBuf {
+    let dir = crate::webview::get_data_dir().join("extension_storage");
+    fs::create_dir_all(&dir).ok();
+    dir.join(format!("{}.json", id))
+}
+
+impl ExtensionStore {
+    /// Scan the extensions folder
+    pub fn load() -> Self {
+        let prefs: ExtensionPrefs = fs::read_to_string(prefs_path()).ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        let mut store = Self { prefs, ..Self::default() };
+
+        let Ok(entries) = fs::read_dir(extensions_dir()) else {
+            return store;
+        };
+        let mut dirs: Vec<PathBuf> = entries.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        dirs.sort();
+
+        for dir in dirs {
+            let id = dir.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                store.errors.push((id, "Folder names may only use letters, digits, - and _".to_string()));
+                continue;
+            }
+            let manifest = fs::read_to_string(dir.join("manifest.json"))
+                .map_err(|e| format!("manifest.json: {}", e))
+                .and_then(|data| serde_json::from_str::<Manifest>(&data).map_err(|e| format!("manifest.json: {}", e)));
+            match manifest {
+                Ok(manifest) => {
+                    let enabled = !store.prefs.disabled.contains(&id);
+                    store.extensions.push(Extension { id, dir, manifest, enabled });
+                }
+                Err(e) => store.errors.push((id, e)),
+            }
+        }
+        info!("Found {} extensions", store.extensions.len());
+        store
+    }
+
+    /// Save which extensions are switched off
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(&self.prefs) {
+            fs::write(prefs_path(), json).ok();
+        }
+    }
+
+    /// Extensions, by folder name
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+
+    /// Folders that are not valid extensions, with the reason
+    pub fn errors(&self) -> &[(String, String)] {
+        &self.errors
+    }
+
+    /// Switch an extension on or off
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) {
+        self.prefs.disabled.retain(|d| d != id);
+        if !enabled {
+            self.prefs.disabled.push(id.to_string());
+        }
+        if let Some(extension) = self.extensions.iter_mut().find(|e| e.id == id) {
+            extension.enabled = enabled;
+        }
+    }
+}
+
+/// Rescan the extensions folder and reinstall
+pub fn reload() {
+    with_extensions(|store| *store = ExtensionStore::load());
+    refresh();
+}
+
+/// Use a content manager for extensions and install the enabled ones
+pub(crate) fn attach(manager: &UserContentManager) {
+    INSTALLED.with(|installed| installed.borrow_mut().manager = Some(manager.clone()));
+    refresh();
+}
+
+/// Reinstall content scripts after extensions changed (applies on next load)
+pub fn refresh() {
+    let extensions: Vec<Extension> = with_extensions(|store| {
+        store.extensions().iter().filter(|e| e.enabled).cloned().collect()
+    });
+
+    INSTALLED.with(|installed| {
+        let mut installed = installed.borrow_mut();
+        let Some(manager) = installed.manager.clone() else {
+            return;
+        };
+        for script in installed.scripts.drain(..) {
+            manager.remove_script(&script);
+        }
+        for sheet in installed.sheets.drain(..) {
+            manager.remove_style_sheet(&sheet);
+        }
+        for (name, world) in installed.handlers.drain(..) {
+            manager.unregister_script_message_handler(&name, Some(&world));
+        }
+
+        for extension in &extensions {
+            install(&mut installed, &manager, extension);
+        }
+    });
+}
+
+/// Install one extension's API shim, scripts and style sheets
+fn install(installed: &mut Installed, manager: &UserContentManager, extension: &Extension) {
+    let world = format!("fos-ext-{}", extension.id);
+    let handler = format!("fosExtension_{}", extension.id);
+    let scripts = &extension.manifest.content_scripts;
+
+    if scripts.iter().any(|cs| !cs.js.is_empty()) {
+        if manager.register_script_message_handler_with_reply(&handler, Some(&world)) {
+            installed.handlers.push((handler.clone(), world.clone()));
+        }
+        if installed.connected.insert(handler.clone()) {
+            let id = extension.id.clone();
+            manager.connect_script_message_with_reply_received(Some(&handler), move |_, message, reply| {
+                match handle_storage(&id, &message.to_str()) {
+                    Ok(json) => {
+                        if let Some(context) = message.context() {
+                            reply.return_value(&javascriptcore::Value::new_string(&context, Some(&json)));
+                        }
+                    }
+                    Err(e) => reply.return_error_message(&e),
+                }
+                true
+            });
+        }
+
+        // Before any of the extension's scripts, wherever they run
+        let all: Vec<String> = scripts.iter().flat_map(|cs| match_patterns(&cs.matches)).collect();
+        let all: Vec<&str> = all.iter().map(String::as_str).collect();
+        let api = API_SCRIPT
+            .replace("__FOS_HANDLER__", &serde_json::to_string(&handler).unwrap_or_default())
+            .replace("__FOS_ID__", &serde_json::to_string(&extension.id).unwrap_or_default());
+        let script = UserScript::for_world(
+            &api,
+            UserContentInjectedFrames::AllFrames,
+            UserScriptInjectionTime::Start,
+            &world,
+            &all,
+            &[],
+        );
+        manager.add_script(&script);
+        installed.scripts.push(script);
+    }
+
+    for cs in scripts {
+        let allow = match_patterns(&cs.matches);
+        let allow: Vec<&str> = allow.iter().map(String::as_str).collect();
+        let block: Vec<&str> = cs.exclude_matches.iter().map(String::as_str).collect();
+        let frames = if cs.all_frames { UserContentInjectedFrames::AllFrames } else { UserContentInjectedFrames::TopFrame };
+
+        let css = read_files(extension, &cs.css);
+        if !css.is_empty() {
+            let sheet = UserStyleSheet::new(&css, frames, UserStyleLevel::User, &allow, &block);
+            manager.add_style_sheet(&sheet);
+            installed.sheets.push(sheet);
+        }
+
+        let js = read_files(extension, &cs.js);
+        if !js.is_empty() {
+            let time = if cs.run_at == "document_start" { UserScriptInjectionTime::Start } else { UserScriptInjectionTime::End };
+            let script = UserScript::for_world(&js, frames, time, &world, &allow, &block);
+            manager.add_script(&script);
+            installed.scripts.push(script);
+        }
+    }
+}
+
+/// WebKit allow-list patterns for manifest `matches`
+fn match_patterns(matches: &[String]) -> Vec<String> {
+    matches.iter()
+        .map(|m| if m == "<all_urls>" { "*://*/*".to_string() } else { m.clone() })
+        .collect()
+}
+
+/// Concatenate files of an extension (paths may not leave its folder)
+fn read_files(extension: &Extension, files: &[String]) -> String {
+    let mut out = String::new();
+    for file in files {
+        let path = Path::new(file);
+        if path.components().any(|c| !matches!(c, Component::Normal(_))) {
+            warn!("Extension {}: ignoring {}", extension.id, file);
+            continue;
+        }
+        match fs::read_to_string(extension.dir.join(path)) {
+            Ok(text) => {
+                out.push_str(&text);
+                out.push_str("\n;\n");
+            }
+            Err(e) => warn!("Extension {}: {}: {}", extension.id, file, e),
+        }
+    }
+    out
+}
+
+/// A storage.local call from the API shim
+#[derive(Deserialize)]
+struct StorageCall {
+    op: String,
+    arg: Value,
+}
+
+fn load_storage(id: &str) -> Map<String, Value> {
+    fs::read_to_string(storage_path(id)).ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_storage(id: &str, data: &Map<String, Value>) -> Result<(), String> {
+    let json = serde_json::to_string(data).map_err(|e| e.to_string())?;
+    if json.len() > MAX_STORAGE_BYTES {
+        return Err("storage.local quota exceeded".to_string());
+    }
+    fs::write(storage_path(id), json).map_err(|e| e.to_string())
+}
+
+/// Bytes an extension keeps in storage.local
+pub fn storage_bytes(id: &str) -> u64 {
+    fs::metadata(storage_path(id)).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Keys given as a string or an array of strings
+fn key_list(arg: &Value) -> Result<Vec<String>, String> {
+    match arg {
+        Value::String(key) => Ok(vec![key.clone()]),
+        Value::Array(keys) => Ok(keys.iter().filter_map(|k| k.as_str().map(str::to_string)).collect()),
+        _ => Err("Keys must be a string or an array of strings".to_string()),
+    }
+}
+
+/// Run a storage.local call and return its result as JSON
+fn handle_storage(id: &str, message: &str) -> Result<String, String> {
+    let call: StorageCall = serde_json::from_str(message).map_err(|e| e.to_string())?;
+    let mut data = load_storage(id);
+
+    let result = match call.op.as_str() {
+        "get" => match call.arg {
+            Value::Null => Value::Object(data),
+            // An object gives defaults for missing keys
+            Value::Object(mut defaults) => {
+                for (key, value) in defaults.iter_mut() {
+                    if let Some(stored) = data.get(key) {
+                        *value = stored.clone();
+                    }
+                }
+                Value::Object(defaults)
+            }
+            arg => Value::Object(key_list(&arg)?.into_iter()
+                .filter_map(|key| data.get(&key).cloned().map(|value| (key, value)))
+                .collect()),
+        },
+        "set" => {
+            let Value::Object(items) = call.arg else {
+                return Err("Items must be an object".to_string());
+            };
+            data.extend(items);
+            save_storage(id, &data)?;
+            Value::Null
+        }
+        "remove" => {
+            for key in key_list(&call.arg)? {
+                data.remove(&key);
+            }
+            save_storage(id, &data)?;
+            Value::Null
+        }
+        "clear" => {
+            save_storage(id, &Map::new())?;
+            Value::Null
+        }
+        op => return Err(format!("Unknown storage operation {}", op)),
+    };
+    Ok(result.to_string())
+}
@@ -0,0 +1,122 @@
+//! Fingerprint Module - Fingerprinting protection
+//!
+//! A script runs in every page's own world before the page's scripts and
+//! makes high-entropy APIs less identifying: canvas readback gets per-site
+//! noise, `navigator.hardwareConcurrency` and screen metrics report common
+//! values, and WebGL hides the GPU vendor and model. The user agent is not
+//! touched, so it stays whatever the user-agent settings say.
+//!
+//! Protection can be turned off for sites it breaks (from the shield panel);
+//! the switches live in the `[fingerprint]` table of config.toml.
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use tracing::info;
+use webkit6::glib;
+use webkit6::{UserContentInjectedFrames, UserContentManager, UserScript, UserScriptInjectionTime};
+
+use crate::omnibox::host_of;
+
+/// Script installed in pages, with `__FOS_SALT__` to be replaced
+const SCRIPT: &str = include_str!("pages/fingerprint-shield.js");
+
+/// Fingerprinting protection switches
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FingerprintConfig {
+    pub enabled: bool,
+    /// Sites (and their subdomains) where protection is off
+    pub disabled_sites: Vec<String>,
+}
+
+impl Default for FingerprintConfig {
+    fn default() -> Self {
+        Self { enabled: true, disabled_sites: Vec::new() }
+    }
+}
+
+impl FingerprintConfig {
+    /// Whether protection applies to the site of a page URL
+    pub fn is_enabled_for(&self, page_url: &str) -> bool {
+        let host = host_of(page_url).to_lowercase();
+        self.enabled && !self.disabled_sites.iter().any(|site| {
+            host == *site || host.strip_suffix(site.as_str()).is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+
+    /// Turn protection on or off for a site, given a host or URL
+    pub fn set_site_enabled(&mut self, site: &str, enabled: bool) {
+        let site = host_of(site.trim()).to_lowercase();
+        if site.is_empty() {
+            return;
+        }
+        self.disabled_sites.retain(|s| *s != site);
+        if !enabled {
+            self.disabled_sites.push(site);
+            self.disabled_sites.sort();
+        }
+    }
+}
+
+/// The installed script, so it can be replaced when the settings change
+#[derive(Default)]
+struct Installed {
+    managers: Vec<UserContentManager>,
+    script: Option<UserScript>,
+    /// Seeds canvas noise for this browser session
+    salt: u32,
+}
+
+// Thread-local install state (since we're running single-threaded GTK)
+thread_local! {
+    static INSTALLED: RefCell<Installed> = RefCell::new(Installed::default());
+}
+
+/// Add a content manager for the protection script and install it
+pub(crate) fn attach(manager: &UserContentManager) {
+    INSTALLED.with(|installed| {
+        let mut installed = installed.borrow_mut();
+        // One salt for the session, shared by private tabs
+        if installed.managers.is_empty() {
+            installed.salt = glib::random_int();
+        }
+        installed.managers.push(manager.clone());
+    });
+    refresh();
+}
+
+/// Reinstall the script after the settings changed (applies on next load)
+pub fn refresh() {
+    let config = crate::config::with_config(|c| c.fingerprint.clone());
+
+    INSTALLED.with(|installed| {
+        let mut installed = installed.borrow_mut();
+        let managers = installed.managers.clone();
+        if let Some(script) = installed.script.take() {
+            for manager in &managers {
+                manager.remove_script(&script);
+            }
+        }
+        if !config.enabled {
+            return;
+        }
+
+        let blocked: Vec<String> = config.disabled_sites.iter()
+            .flat_map(|site| [format!("*://{}/*", site), format!("*://*.{}/*", site)])
+            .collect();
+        let blocked: Vec<&str> = blocked.iter().map(String::as_str).collect();
+        let source = SCRIPT.replace("__FOS_SALT__", &installed.salt.to_string());
+        let script = UserScript::new(
+            &source,
+            UserContentInjectedFrames::AllFrames,
+            UserScriptInjectionTime::Start,
+            &[],
+            &blocked,
+        );
+        for manager in &managers {
+            manager.add_script(&script);
+        }
+        installed.script = Some(script);
+        info!("Fingerprinting protection on ({} sites excepted)", config.disabled_sites.len());
+    });
+}
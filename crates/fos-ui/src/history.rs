@@ -0,0 +1,145 @@
+//! History Module
+//!
+//! Features:
+//! - Records visited pages with visit count and last visit time
+//! - Persisted as JSON in the data directory
+//! - Bounded size: the least recently visited entry is dropped first
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum number of history entries kept on disk
+const MAX_ENTRIES: usize = 5000;
+
+/// A visited page
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub title: String,
+    pub visit_count: u32,
+    /// Unix timestamp (seconds)
+    pub last_visit: u64,
+}
+
+/// Browsing history
+#[derive(Serialize, Deserialize, Default)]
+pub struct HistoryStore {
+    entries: Vec<HistoryEntry>,
+    /// Changed since the last save
+    #[serde(skip)]
+    dirty: bool,
+}
+
+// Thread-local store (since we're running single-threaded GTK)
+thread_local! {
+    static HISTORY: RefCell<HistoryStore> = RefCell::new(HistoryStore::load());
+}
+
+/// Run a closure with the history store
+pub fn with_history<R>(f: impl FnOnce(&mut HistoryStore) -> R) -> R {
+    HISTORY.with(|store| f(&mut store.borrow_mut()))
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl HistoryStore {
+    /// Load history from the data directory
+    pub fn load() -> Self {
+        let path = crate::webview::get_data_dir().join("history.json");
+        if let Ok(data) = fs::read_to_string(&path) {
+            serde_json::from_str(&data).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Save history to the data directory
+    pub fn save(&mut self) {
+        let path = crate::webview::get_data_dir().join("history.json");
+        if let Ok(json) = serde_json::to_string(self) {
+            fs::write(path, json).ok();
+        }
+        self.dirty = false;
+    }
+
+    /// Save history if it changed since the last save
+    pub fn save_if_dirty(&mut self) {
+        if self.dirty {
+            self.save();
+        }
+    }
+
+    /// Record a visit to a page
+    pub fn record_visit(&mut self, url: &str, title: &str) {
+        let now = now_secs();
+        self.dirty = true;
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.url == url) {
+            entry.visit_count += 1;
+            entry.last_visit = now;
+            if !title.is_empty() {
+                entry.title = title.to_string();
+            }
+            return;
+        }
+
+        self.entries.push(HistoryEntry {
+            url: url.to_string(),
+            title: title.to_string(),
+            visit_count: 1,
+            last_visit: now,
+        });
+
+        if self.entries.len() > MAX_ENTRIES {
+            // Drop the least recently visited entry
+            if let Some(oldest) = self.entries.iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.last_visit)
+                .map(|(i, _)| i)
+            {
+                self.entries.remove(oldest);
+            }
+        }
+    }
+
+    /// Update the title of a visited page (titles arrive after the load)
+    pub fn update_title(&mut self, url: &str, title: &str) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.url == url) {
+            entry.title = title.to_string();
+            self.dirty = true;
+        }
+    }
+
+    /// All entries, in no particular order (revisits update an entry in place)
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Remove every entry, returning what was removed
+    pub fn clear(&mut self) -> Vec<HistoryEntry> {
+        self.dirty = true;
+        std::mem::take(&mut self.entries)
+    }
+
+    /// Put back cleared entries (used by undo), merging with visits made since
+    pub fn restore(&mut self, entries: Vec<HistoryEntry>) {
+        self.dirty = true;
+        for old in entries {
+            if let Some(entry) = self.entries.iter_mut().find(|e| e.url == old.url) {
+                entry.visit_count += old.visit_count;
+                if entry.title.is_empty() {
+                    entry.title = old.title;
+                }
+            } else {
+                self.entries.push(old);
+            }
+        }
+        self.entries.sort_by_key(|e| e.last_visit);
+    }
+}
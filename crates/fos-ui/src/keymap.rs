@@ -0,0 +1,370 @@
+//! Keymap Module - Customizable keyboard shortcuts
+//!
+//! Every browser shortcut is an `Action` with a default key chord. The
+//! `[keys]` table of config.toml overrides them by action id, e.g.
+//! `back = "Alt+Left"` (an empty string unbinds the action), so edits apply
+//! live like the rest of the settings. Chords bound to more than one action
+//! are reported as conflicts; the first action in `Action::ALL` wins.
+
+use gtk4::gdk::{Key, ModifierType};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Modifiers that take part in a chord (lock keys are ignored)
+const CHORD_MODIFIERS: ModifierType = ModifierType::CONTROL_MASK
+    .union(ModifierType::SHIFT_MASK)
+    .union(ModifierType::ALT_MASK)
+    .union(ModifierType::SUPER_MASK);
+
+/// A browser command that can be bound to a key chord
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    NewTab,
+    NewPrivateTab,
+    CloseTab,
+    Reload,
+    FocusUrl,
+    PreviousTab,
+    NextTab,
+    Back,
+    Forward,
+    Bookmark,
+    Mute,
+    Find,
+    Undo,
+    ClearHistory,
+    Reader,
+    GroupTab,
+    NextGroup,
+}
+
+impl Action {
+    /// Every action, in settings page order
+    pub const ALL: [Action; 17] = [
+        Action::NewTab,
+        Action::NewPrivateTab,
+        Action::CloseTab,
+        Action::Reload,
+        Action::FocusUrl,
+        Action::PreviousTab,
+        Action::NextTab,
+        Action::Back,
+        Action::Forward,
+        Action::Bookmark,
+        Action::Mute,
+        Action::Find,
+        Action::Undo,
+        Action::ClearHistory,
+        Action::Reader,
+        Action::GroupTab,
+        Action::NextGroup,
+    ];
+
+    /// Name used in config.toml
+    pub fn id(self) -> &'static str {
+        match self {
+            Action::NewTab => "new-tab",
+            Action::NewPrivateTab => "new-private-tab",
+            Action::CloseTab => "close-tab",
+            Action::Reload => "reload",
+            Action::FocusUrl => "focus-url",
+            Action::PreviousTab => "previous-tab",
+            Action::NextTab => "next-tab",
+            Action::Back => "back",
+            Action::Forward => "forward",
+            Action::Bookmark => "bookmark",
+            Action::Mute => "mute",
+            Action::Find => "find",
+            Action::Undo => "undo",
+            Action::ClearHistory => "clear-history",
+            Action::Reader => "reader",
+            Action::GroupTab => "group-tab",
+            Action::NextGroup => "next-group",
+        }
+    }
+
+    /// Human-readable description
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::NewTab => "New tab",
+            Action::NewPrivateTab => "New private tab",
+            Action::CloseTab => "Close tab",
+            Action::Reload => "Reload page",
+            Action::FocusUrl => "Focus URL bar",
+            Action::PreviousTab => "Switch to tab above",
+            Action::NextTab => "Switch to tab below",
+            Action::Back => "Go back",
+            Action::Forward => "Go forward",
+            Action::Bookmark => "Bookmark page",
+            Action::Mute => "Mute/unmute tab",
+            Action::Find => "Find in page",
+            Action::Undo => "Undo",
+            Action::ClearHistory => "Clear history",
+            Action::Reader => "Toggle reader mode",
+            Action::GroupTab => "Group/ungroup tab",
+            Action::NextGroup => "Next tab group",
+        }
+    }
+
+    /// Chord used when config.toml does not override it
+    pub fn default_chord(self) -> &'static str {
+        match self {
+            Action::NewTab => "Ctrl+T",
+            Action::NewPrivateTab => "Ctrl+Shift+N",
+            Action::CloseTab => "Ctrl+W",
+            Action::Reload => "Ctrl+R",
+            Action::FocusUrl => "Ctrl+I",
+            Action::PreviousTab => "Ctrl+O",
+            Action::NextTab => "Ctrl+L",
+            Action::Back => "Ctrl+K",
+            Action::Forward => "Ctrl+Ñ",
+            Action::Bookmark => "Ctrl+D",
+            Action::Mute => "Ctrl+M",
+            Action::Find => "Ctrl+F",
+            Action::Undo => "Ctrl+Z",
+            Action::ClearHistory => "Ctrl+Shift+Delete",
+            Action::Reader => "F9",
+            Action::GroupTab => "Ctrl+G",
+            Action::NextGroup => "Ctrl+Shift+G",
+        }
+    }
+
+    /// Look up an action by its config.toml name
+    pub fn from_id(id: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.id() == id)
+    }
+}
+
+/// The key of a chord
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ChordKey {
+    /// A printable key, lower-case so Shift does not change it
+    Char(char),
+    /// A named key such as `F9`, `Delete` or `Left`
+    Named(Key),
+}
+
+/// A key plus modifiers, written like `Ctrl+Shift+N`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    key: ChordKey,
+    modifiers: ModifierType,
+}
+
+impl Chord {
+    /// Parse `Ctrl+Shift+N`, `Alt+Left`, `F9` or `Ctrl+ñ`
+    pub fn parse(text: &str) -> Option<Chord> {
+        let (mods, key) = text.trim().rsplit_once('+')
+            .filter(|(_, key)| !key.is_empty())
+            .unwrap_or(("", text.trim()));
+        let key = key.trim();
+
+        let mut modifiers = ModifierType::empty();
+        for part in mods.split('+').map(str::trim).filter(|p| !p.is_empty()) {
+            modifiers |= match part.to_lowercase().as_str() {
+                "ctrl" | "control" => ModifierType::CONTROL_MASK,
+                "shift" => ModifierType::SHIFT_MASK,
+                "alt" => ModifierType::ALT_MASK,
+                "super" | "meta" => ModifierType::SUPER_MASK,
+                _ => return None,
+            };
+        }
+
+        let mut chars = key.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_control() => ChordKey::Char(c.to_lowercase().next()?),
+            _ => {
+                let named = Key::from_name(key)?;
+                // Names of printable keys ("ntilde") mean their character;
+                // Delete, Escape, Tab... map to control characters and stay named
+                match named.to_lower().to_unicode().filter(|c| !c.is_whitespace() && !c.is_control()) {
+                    Some(c) => ChordKey::Char(c),
+                    None => ChordKey::Named(named),
+                }
+            }
+        };
+        Some(Chord { key, modifiers })
+    }
+
+    /// Whether a key press is this chord
+    pub fn matches(&self, key: Key, modifiers: ModifierType) -> bool {
+        let same_key = match self.key {
+            ChordKey::Char(c) => key.to_lower().to_unicode() == Some(c),
+            ChordKey::Named(named) => key == named,
+        };
+        same_key && modifiers & CHORD_MODIFIERS == self.modifiers
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (mask, name) in [
+            (ModifierType::CONTROL_MASK, "Ctrl+"),
+            (ModifierType::SHIFT_MASK, "Shift+"),
+            (ModifierType::ALT_MASK, "Alt+"),
+            (ModifierType::SUPER_MASK, "Super+"),
+        ] {
+            if self.modifiers.contains(mask) {
+                f.write_str(name)?;
+            }
+        }
+        match self.key {
+            ChordKey::Char(c) => write!(f, "{}", c.to_uppercase()),
+            ChordKey::Named(key) => write!(f, "{}", key.name().unwrap_or_default()),
+        }
+    }
+}
+
+/// Shortcut overrides from the `[keys]` table of config.toml
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub struct KeymapConfig {
+    /// Action id -> chord; actions not listed use their default
+    bindings: BTreeMap<String, String>,
+}
+
+impl KeymapConfig {
+    /// Text of the chord bound to an action (empty if unbound)
+    pub fn binding(&self, action: Action) -> &str {
+        self.bindings.get(action.id())
+            .map(String::as_str)
+            .unwrap_or(action.default_chord())
+    }
+
+    /// Chord bound to an action (None if unbound or unparsable)
+    pub fn chord(&self, action: Action) -> Option<Chord> {
+        Chord::parse(self.binding(action))
+    }
+
+    /// Action bound to a key press
+    pub fn action_for(&self, key: Key, modifiers: ModifierType) -> Option<Action> {
+        Action::ALL.into_iter()
+            .find(|a| self.chord(*a).is_some_and(|c| c.matches(key, modifiers)))
+    }
+
+    /// Bind an action to a chord ("" unbinds it). Returns the action that
+    /// already uses the chord, without changing anything, on a conflict.
+    pub fn bind(&mut self, action: Action, chord: &str) -> Result<(), BindError> {
+        let chord = chord.trim();
+        if chord.is_empty() {
+            self.bindings.insert(action.id().to_string(), String::new());
+            return Ok(());
+        }
+        let parsed = Chord::parse(chord).ok_or(BindError::Invalid)?;
+        if let Some(other) = Action::ALL.into_iter()
+            .find(|a| *a != action && self.chord(*a) == Some(parsed))
+        {
+            return Err(BindError::Conflict(other));
+        }
+        // Keep the file short: defaults are not written out
+        if Chord::parse(action.default_chord()) == Some(parsed) {
+            self.bindings.remove(action.id());
+        } else {
+            self.bindings.insert(action.id().to_string(), parsed.to_string());
+        }
+        Ok(())
+    }
+
+    /// Go back to the default chord
+    pub fn reset(&mut self, action: Action) {
+        self.bindings.remove(action.id());
+    }
+
+    /// Chords bound to several actions, and entries that cannot be used
+    /// (unknown action or unparsable chord), as messages
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (id, chord) in &self.bindings {
+            match Action::from_id(id) {
+                None => problems.push(format!("Unknown action \"{}\"", id)),
+                Some(_) if !chord.is_empty() && Chord::parse(chord).is_none() => {
+                    problems.push(format!("Invalid shortcut \"{}\" for {}", chord, id));
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut seen: Vec<(Chord, Action)> = Vec::new();
+        for action in Action::ALL {
+            let Some(chord) = self.chord(action) else {
+                continue;
+            };
+            match seen.iter().find(|(c, _)| *c == chord) {
+                Some((_, first)) => problems.push(format!(
+                    "{} is bound to both {} and {}; {} wins",
+                    chord, first.id(), action.id(), first.id()
+                )),
+                None => seen.push((chord, action)),
+            }
+        }
+        problems
+    }
+}
+
+/// Why a shortcut could not be bound
+pub enum BindError {
+    /// Not a chord like `Ctrl+Shift+N`
+    Invalid,
+    /// Already used by another action
+    Conflict(Action),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse, display, and parse the display again
+    fn round_trip(text: &str) -> (Chord, String, Chord) {
+        let chord = Chord::parse(text).unwrap_or_else(|| panic!("{} should parse", text));
+        let shown = chord.to_string();
+        let again = Chord::parse(&shown).unwrap_or_else(|| panic!("{} (from {}) should parse", shown, text));
+        (chord, shown, again)
+    }
+
+    #[test]
+    fn chords_round_trip() {
+        for text in ["Ctrl+Shift+N", "Alt+Left", "F9", "Ctrl+ñ", "Ctrl+Z", "Super+1", "Ctrl+Shift+Delete"] {
+            let (chord, _, again) = round_trip(text);
+            assert!(chord == again, "{} changed after a round trip", text);
+        }
+    }
+
+    #[test]
+    fn control_keys_stay_named() {
+        for name in ["Delete", "Escape", "BackSpace", "Tab", "Return"] {
+            let (chord, shown, again) = round_trip(name);
+            assert_eq!(shown, name);
+            assert!(!shown.chars().any(char::is_control));
+            assert!(matches!(chord.key, ChordKey::Named(_)), "{} parsed as a character", name);
+            assert!(chord == again);
+        }
+    }
+
+    #[test]
+    fn display_is_canonical() {
+        assert_eq!(Chord::parse("shift+ctrl+n").map(|c| c.to_string()).as_deref(), Some("Ctrl+Shift+N"));
+        assert_eq!(Chord::parse("Ctrl+ntilde").map(|c| c.to_string()).as_deref(), Some("Ctrl+Ñ"));
+        assert_eq!(Chord::parse(" Alt + Left ").map(|c| c.to_string()).as_deref(), Some("Alt+Left"));
+    }
+
+    #[test]
+    fn invalid_chords_are_rejected() {
+        assert!(Chord::parse("").is_none());
+        assert!(Chord::parse("Hyper+X").is_none());
+        assert!(Chord::parse("Ctrl+NotAKey").is_none());
+        assert!(Chord::parse("\u{7f}").is_none());
+    }
+
+    #[test]
+    fn default_chords_round_trip() {
+        for action in Action::ALL {
+            let default = action.default_chord();
+            if default.is_empty() {
+                continue;
+            }
+            let (chord, _, again) = round_trip(default);
+            assert!(chord == again, "default of {} changed after a round trip", action.id());
+        }
+    }
+}
@@ -5,6 +5,49 @@
 
 mod webview;
 mod adblocker;
+mod archive;
+mod blockstats;
+mod bookmarks;
+mod config;
+mod extensions;
+mod fingerprint;
+mod history;
+mod keymap;
+mod media;
+mod metrics;
+mod omnibox;
+mod prefetch;
+mod protocol;
+mod reader;
+mod scripting;
+mod search;
+mod shield;
+mod thumbnails;
+mod undo;
+mod user_agent;
+mod user_styles;
+mod zoom;
 
 pub use webview::{run_webview, WebBrowser};
-pub use adblocker::{should_block, init as init_adblocker};
+pub use adblocker::{
+    should_block, check as check_request, init as init_adblocker,
+    TrackerCategory, BlockingPrefs, with_blocking_prefs,
+};
+pub use archive::{export_archive, import_archive, section_names as archive_sections};
+pub use blockstats::{BlockStats, DayStats, Report as BlockReport, weekly_report, format_bytes};
+pub use bookmarks::{
+    Bookmark, BookmarkStore, Folder, with_bookmarks,
+    import_file as import_bookmarks, export_file as export_bookmarks,
+};
+pub use config::{Config, AdblockConfig, with_config};
+pub use history::{HistoryEntry, HistoryStore, with_history};
+pub use metrics::{Registry as MetricsRegistry, with_metrics, LATENCY_BUCKETS};
+pub use scripting::{run as run_internal_script, inject as inject_internal_script, ScriptError};
+pub use search::{SearchEngine, SearchEngineManager, with_search_engines, search_url};
+pub use shield::{PageShield, page as page_shield};
+pub use thumbnails::{ThumbnailCache, with_thumbnails};
+pub use reader::{Article, ReaderStore, with_reader, parse_article};
+pub use undo::{register as register_undo, undo_last, UndoStack, UNDO_TIMEOUT};
+pub use user_styles::{UserStyle, UserStyleStore, with_user_styles};
+pub use zoom::{ZoomStore, with_zoom};
+pub use omnibox::{Suggestion, SuggestionKind, suggest, rank};
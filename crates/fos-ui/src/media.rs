@@ -0,0 +1,204 @@
+//! Media Module - Media keys through MPRIS
+//!
+//! The browser registers as an MPRIS player on the session bus, so desktop
+//! media keys and sound menus can play and pause the tab that last played
+//! sound. Commands run as internal scripts on the page's `<video>` and
+//! `<audio>` elements.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tracing::{info, warn};
+use webkit6::gio::{self, prelude::*};
+use webkit6::glib::{self, Variant};
+
+/// Well-known bus name of the player
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.fos_wb";
+
+/// Object path required by the MPRIS specification
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// The parts of the MPRIS interfaces we implement
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.mpris.MediaPlayer2">
+    <method name="Raise"/>
+    <method name="Quit"/>
+    <property name="CanQuit" type="b" access="read"/>
+    <property name="CanRaise" type="b" access="read"/>
+    <property name="HasTrackList" type="b" access="read"/>
+    <property name="Identity" type="s" access="read"/>
+    <property name="DesktopEntry" type="s" access="read"/>
+    <property name="SupportedUriSchemes" type="as" access="read"/>
+    <property name="SupportedMimeTypes" type="as" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="Next"/>
+    <method name="Previous"/>
+    <method name="Pause"/>
+    <method name="PlayPause"/>
+    <method name="Stop"/>
+    <method name="Play"/>
+    <property name="PlaybackStatus" type="s" access="read"/>
+    <property name="Metadata" type="a{sv}" access="read"/>
+    <property name="Rate" type="d" access="read"/>
+    <property name="MinimumRate" type="d" access="read"/>
+    <property name="MaximumRate" type="d" access="read"/>
+    <property name="CanGoNext" type="b" access="read"/>
+    <property name="CanGoPrevious" type="b" access="read"/>
+    <property name="CanPlay" type="b" access="read"/>
+    <property name="CanPause" type="b" access="read"/>
+    <property name="CanSeek" type="b" access="read"/>
+    <property name="CanControl" type="b" access="read"/>
+  </interface>
+</node>
+"#;
+
+/// A playback command from a media key or sound menu
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MediaCommand {
+    PlayPause,
+    Play,
+    Pause,
+    Stop,
+}
+
+impl MediaCommand {
+    fn from_method(name: &str) -> Option<Self> {
+        match name {
+            "PlayPause" => Some(Self::PlayPause),
+            "Play" => Some(Self::Play),
+            "Pause" => Some(Self::Pause),
+            "Stop" => Some(Self::Stop),
+            _ => None,
+        }
+    }
+
+    /// Internal script applying the command to the page's media elements
+    pub fn script(self) -> &'static str {
+        match self {
+            Self::PlayPause => "(() => { const media = [...document.querySelectorAll('video, audio')]; \
+                const playing = media.filter(m => !m.paused); \
+                if (playing.length) playing.forEach(m => m.pause()); else if (media[0]) media[0].play(); })()",
+            Self::Play => "(() => { const m = document.querySelector('video, audio'); if (m) m.play(); })()",
+            Self::Pause => "document.querySelectorAll('video, audio').forEach(m => m.pause())",
+            Self::Stop => "document.querySelectorAll('video, audio').forEach(m => { m.pause(); m.currentTime = 0; })",
+        }
+    }
+}
+
+/// What the player reports to the desktop
+#[derive(Default)]
+struct Status {
+    playing: bool,
+    title: String,
+}
+
+// Thread-local bus state (since we're running single-threaded GTK)
+thread_local! {
+    static CONNECTION: RefCell<Option<gio::DBusConnection>> = const { RefCell::new(None) };
+    static STATUS: RefCell<Status> = RefCell::new(Status::default());
+}
+
+/// Register the MPRIS player; `on_command` runs for every media key press
+pub(crate) fn export(on_command: impl Fn(MediaCommand) + 'static) {
+    let node = match gio::DBusNodeInfo::for_xml(INTROSPECTION) {
+        Ok(node) => node,
+        Err(e) => {
+            warn!("Invalid MPRIS introspection data: {}", e);
+            return;
+        }
+    };
+    let on_command: Rc<dyn Fn(MediaCommand)> = Rc::new(on_command);
+
+    gio::bus_own_name(
+        gio::BusType::Session,
+        BUS_NAME,
+        gio::BusNameOwnerFlags::NONE,
+        move |connection, _| {
+            for name in [ROOT_INTERFACE, PLAYER_INTERFACE] {
+                let Some(interface) = node.lookup_interface(name) else {
+                    continue;
+                };
+                let on_command = on_command.clone();
+                let registered = connection.register_object(OBJECT_PATH, &interface)
+                    .method_call(move |_, _, _, _, method, _, invocation| {
+                        if let Some(command) = MediaCommand::from_method(method) {
+                            on_command(command);
+                        }
+                        // Raise, Quit, Next and Previous are accepted but do nothing
+                        invocation.return_value(None);
+                    })
+                    .property(|_, _, _, _, property| property_value(property))
+                    .build();
+                if let Err(e) = registered {
+                    warn!("Cannot export MPRIS {}: {}", name, e);
+                }
+            }
+            CONNECTION.with(|c| *c.borrow_mut() = Some(connection));
+        },
+        |_, name| info!("Media keys available as {}", name),
+        |_, name| warn!("Could not own {}; media keys disabled", name),
+    );
+}
+
+/// Tell the desktop whether media is playing, and in which page
+pub(crate) fn set_status(playing: bool, title: &str) {
+    let changed = STATUS.with(|status| {
+        let mut status = status.borrow_mut();
+        let changed = status.playing != playing || status.title != title;
+        status.playing = playing;
+        status.title = title.to_string();
+        changed
+    });
+    if !changed {
+        return;
+    }
+
+    CONNECTION.with(|connection| {
+        let Some(connection) = connection.borrow().clone() else {
+            return;
+        };
+        let changed: HashMap<String, Variant> = ["PlaybackStatus", "Metadata"].iter()
+            .map(|p| (p.to_string(), property_value(p)))
+            .collect();
+        let parameters = (PLAYER_INTERFACE, changed, Vec::<String>::new()).to_variant();
+        if let Err(e) = connection.emit_signal(
+            None,
+            OBJECT_PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            Some(&parameters),
+        ) {
+            warn!("Cannot update MPRIS status: {}", e);
+        }
+    });
+}
+
+fn property_value(property: &str) -> Variant {
+    match property {
+        "CanQuit" | "CanRaise" | "HasTrackList" | "CanGoNext" | "CanGoPrevious" | "CanSeek" => false.to_variant(),
+        "CanPlay" | "CanPause" | "CanControl" => true.to_variant(),
+        "Identity" => "fOS-WB".to_variant(),
+        "DesktopEntry" => "fos-wb".to_variant(),
+        "SupportedUriSchemes" | "SupportedMimeTypes" => Vec::<String>::new().to_variant(),
+        "Rate" | "MinimumRate" | "MaximumRate" => 1.0f64.to_variant(),
+        "PlaybackStatus" => STATUS.with(|s| if s.borrow().playing { "Playing" } else { "Paused" }).to_variant(),
+        "Metadata" => {
+            let mut metadata: HashMap<String, Variant> = HashMap::new();
+            let track = glib::variant::ObjectPath::try_from(format!("{}/TrackList/NoTrack", OBJECT_PATH))
+                .map(|p| p.to_variant())
+                .unwrap_or_else(|_| "".to_variant());
+            metadata.insert("mpris:trackid".to_string(), track);
+            let title = STATUS.with(|s| s.borrow().title.clone());
+            if !title.is_empty() {
+                metadata.insert("xesam:title".to_string(), title.to_variant());
+            }
+            metadata.to_variant()
+        }
+        _ => "".to_variant(),
+    }
+}
@@ -0,0 +1,196 @@
+//! Metrics Module - In-process counters, gauges and histograms
+//!
+//! A small registry the other modules report into (blocked requests, page
+//! load times, open tabs, crashes, thumbnail memory). Nothing leaves the
+//! machine: the values are only shown on fos://metrics, in the Prometheus
+//! text format, for debugging memory and latency regressions.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+
+/// Bucket bounds (seconds) used by latency histograms
+pub const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Values of one labelled series
+enum Series {
+    Counter(u64),
+    Gauge(f64),
+    Histogram { buckets: Vec<u64>, sum: f64, count: u64 },
+}
+
+/// A metric name with its help text and labelled series
+struct Family {
+    help: &'static str,
+    /// Keyed by the rendered label set (`category="advertising"`, or empty)
+    series: BTreeMap<String, Series>,
+}
+
+/// All metrics, by name
+#[derive(Default)]
+pub struct Registry {
+    families: BTreeMap<&'static str, Family>,
+}
+
+// Thread-local registry (since we're running single-threaded GTK)
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+/// Run a closure with the metrics registry
+pub fn with_metrics<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    REGISTRY.with(|registry| f(&mut registry.borrow_mut()))
+}
+
+/// Add one to a counter
+pub fn inc(name: &'static str, help: &'static str, labels: &[(&str, &str)]) {
+    with_metrics(|m| m.add(name, help, labels, 1));
+}
+
+/// Set a gauge
+pub fn set(name: &'static str, help: &'static str, value: f64) {
+    with_metrics(|m| m.set(name, help, value));
+}
+
+/// Record a duration (seconds) in a latency histogram
+pub fn observe(name: &'static str, help: &'static str, seconds: f64) {
+    with_metrics(|m| m.observe(name, help, seconds));
+}
+
+impl Registry {
+    fn series(&mut self, name: &'static str, help: &'static str, labels: &[(&str, &str)], new: Series) -> &mut Series {
+        let key = labels.iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.families.entry(name)
+            .or_insert_with(|| Family { help, series: BTreeMap::new() })
+            .series
+            .entry(key)
+            .or_insert(new)
+    }
+
+    /// Add to a counter
+    pub fn add(&mut self, name: &'static str, help: &'static str, labels: &[(&str, &str)], n: u64) {
+        if let Series::Counter(value) = self.series(name, help, labels, Series::Counter(0)) {
+            *value += n;
+        }
+    }
+
+    /// Set a gauge
+    pub fn set(&mut self, name: &'static str, help: &'static str, value: f64) {
+        if let Series::Gauge(gauge) = self.series(name, help, &[], Series::Gauge(0.0)) {
+            *gauge = value;
+        }
+    }
+
+    /// Record a value in a histogram with `LATENCY_BUCKETS`
+    pub fn observe(&mut self, name: &'static str, help: &'static str, value: f64) {
+        let empty = Series::Histogram { buckets: vec![0; LATENCY_BUCKETS.len()], sum: 0.0, count: 0 };
+        if let Series::Histogram { buckets, sum, count } = self.series(name, help, &[], empty) {
+            for (bucket, bound) in buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                if value <= *bound {
+                    *bucket += 1;
+                }
+            }
+            *sum += value;
+            *count += 1;
+        }
+    }
+
+    /// Current value of an unlabelled gauge
+    pub fn gauge(&self, name: &str) -> Option<f64> {
+        match self.families.get(name)?.series.get("")? {
+            Series::Gauge(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Sum of a counter over all its labels
+    pub fn counter_total(&self, name: &str) -> u64 {
+        self.families.get(name)
+            .map(|family| family.series.values()
+                .map(|series| if let Series::Counter(n) = series { *n } else { 0 })
+                .sum())
+            .unwrap_or(0)
+    }
+
+    /// Render every metric in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in &self.families {
+            let kind = match family.series.values().next() {
+                Some(Series::Counter(_)) => "counter",
+                Some(Series::Gauge(_)) => "gauge",
+                Some(Series::Histogram { .. }) => "histogram",
+                None => continue,
+            };
+            let _ = writeln!(out, "# HELP {} {}", name, family.help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+
+            for (labels, series) in &family.series {
+                let braces = |extra: &str| {
+                    let all = [labels.as_str(), extra].iter()
+                        .filter(|s| !s.is_empty())
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    if all.is_empty() { String::new() } else { format!("{{{}}}", all) }
+                };
+                match series {
+                    Series::Counter(value) => {
+                        let _ = writeln!(out, "{}{} {}", name, braces(""), value);
+                    }
+                    Series::Gauge(value) => {
+                        let _ = writeln!(out, "{}{} {}", name, braces(""), value);
+                    }
+                    Series::Histogram { buckets, sum, count } => {
+                        for (bound, n) in LATENCY_BUCKETS.iter().zip(buckets) {
+                            let le = format!("le=\"{}\"", bound);
+                            let _ = writeln!(out, "{}_bucket{} {}", name, braces(&le), n);
+                        }
+                        let _ = writeln!(out, "{}_bucket{} {}", name, braces("le=\"+Inf\""), count);
+                        let _ = writeln!(out, "{}_sum{} {}", name, braces(""), sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, braces(""), count);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Resident memory of a process in bytes, from /proc (Linux only)
+pub fn process_rss(pid: u32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let kb = status.lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Child processes of the browser (WebKit's web and network processes)
+/// as (pid, name, resident bytes)
+pub fn child_processes() -> Vec<(u32, String, u64)> {
+    let me = std::process::id();
+    let Ok(dir) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut children: Vec<(u32, String, u64)> = dir
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            // stat: "pid (name) state ppid ..." - the name may contain spaces
+            let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            let (name, rest) = stat.split_once(" (")?.1.rsplit_once(") ")?;
+            let ppid: u32 = rest.split_whitespace().nth(1)?.parse().ok()?;
+            (ppid == me).then(|| (pid, name.to_string(), process_rss(pid).unwrap_or(0)))
+        })
+        .collect();
+    children.sort();
+    children
+}
@@ -0,0 +1,223 @@
+//! Omnibox Module - Address bar suggestions
+//!
+//! Merges history, bookmarks and open tabs into one ranked list.
+//! Ranking uses frecency (how often and how recently a page was visited),
+//! boosted for bookmarks, open tabs and typed-host prefix matches.
+
+use std::collections::HashMap;
+
+use crate::bookmarks::{with_bookmarks, Bookmark};
+use crate::history::{now_secs, with_history, HistoryEntry};
+
+/// Maximum number of suggestions shown
+const MAX_SUGGESTIONS: usize = 8;
+
+/// Score bonus for bookmarked pages
+const BOOKMARK_BONUS: f64 = 150.0;
+
+/// Score bonus for pages already open in a tab
+const OPEN_TAB_BONUS: f64 = 100.0;
+
+/// Where a suggestion came from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SuggestionKind {
+    OpenTab,
+    Bookmark,
+    History,
+}
+
+/// A ranked address bar suggestion
+#[derive(Clone, Debug)]
+pub struct Suggestion {
+    pub url: String,
+    pub title: String,
+    pub kind: SuggestionKind,
+    pub score: f64,
+}
+
+/// Suggestions for what the user typed, using the global stores
+pub fn suggest(query: &str, open_tabs: &[(String, String)]) -> Vec<Suggestion> {
+    with_history(|history| {
+        with_bookmarks(|bookmarks| {
+            rank(query, history.entries(), bookmarks.bookmarks(), open_tabs, now_secs())
+        })
+    })
+}
+
+/// Rank candidates from every source for a query
+pub fn rank(
+    query: &str,
+    history: &[HistoryEntry],
+    bookmarks: &[Bookmark],
+    open_tabs: &[(String, String)],
+    now: u64,
+) -> Vec<Suggestion> {
+    let words: Vec<String> = query.split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut merged: HashMap<&str, Suggestion> = HashMap::new();
+
+    for entry in history {
+        if let Some(quality) = match_quality(&words, &entry.url, &entry.title) {
+            let score = frecency(entry.visit_count, entry.last_visit, now) * quality;
+            merge(&mut merged, &entry.url, &entry.title, SuggestionKind::History, score);
+        }
+    }
+
+    for bookmark in bookmarks {
+        if let Some(quality) = match_quality(&words, &bookmark.url, &bookmark.title) {
+            merge(&mut merged, &bookmark.url, &bookmark.title, SuggestionKind::Bookmark, BOOKMARK_BONUS * quality);
+        }
+    }
+
+    for (url, title) in open_tabs {
+        if let Some(quality) = match_quality(&words, url, title) {
+            merge(&mut merged, url, title, SuggestionKind::OpenTab, OPEN_TAB_BONUS * quality);
+        }
+    }
+
+    let mut suggestions: Vec<Suggestion> = merged.into_values().collect();
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// Add a candidate, combining scores when the URL was already seen.
+/// The displayed kind prefers open tabs, then bookmarks, then history.
+fn merge<'a>(
+    merged: &mut HashMap<&'a str, Suggestion>,
+    url: &'a str,
+    title: &str,
+    kind: SuggestionKind,
+    score: f64,
+) {
+    let entry = merged.entry(url).or_insert_with(|| Suggestion {
+        url: url.to_string(),
+        title: title.to_string(),
+        kind,
+        score: 0.0,
+    });
+
+    entry.score += score;
+    if kind_rank(kind) < kind_rank(entry.kind) {
+        entry.kind = kind;
+    }
+    if entry.title.is_empty() {
+        entry.title = title.to_string();
+    }
+}
+
+fn kind_rank(kind: SuggestionKind) -> u8 {
+    match kind {
+        SuggestionKind::OpenTab => 0,
+        SuggestionKind::Bookmark => 1,
+        SuggestionKind::History => 2,
+    }
+}
+
+/// Frecency: visit count weighted by how recent the last visit was
+fn frecency(visit_count: u32, last_visit: u64, now: u64) -> f64 {
+    let age_days = now.saturating_sub(last_visit) / 86_400;
+    let recency = match age_days {
+        0..=3 => 100.0,
+        4..=14 => 70.0,
+        15..=31 => 50.0,
+        32..=90 => 30.0,
+        _ => 10.0,
+    };
+    f64::from(visit_count.max(1)) * recency
+}
+
+/// How well a page matches: `None` if any word is missing,
+/// higher when the host starts with what the user is typing
+fn match_quality(words: &[String], url: &str, title: &str) -> Option<f64> {
+    let url_lower = url.to_lowercase();
+    let title_lower = title.to_lowercase();
+    if !words.iter().all(|w| url_lower.contains(w) || title_lower.contains(w)) {
+        return None;
+    }
+
+    let host = host_of(&url_lower);
+    if host.starts_with(words[0].as_str()) {
+        Some(3.0)
+    } else if url_lower.contains(words[0].as_str()) {
+        Some(1.5)
+    } else {
+        Some(1.0)
+    }
+}
+
+/// Host part of a URL without `www.`
+pub(crate) fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    host.strip_prefix("www.").unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+    const DAY: u64 = 86_400;
+
+    fn visit(url: &str, title: &str, visit_count: u32, days_ago: u64) -> HistoryEntry {
+        HistoryEntry {
+            url: url.to_string(),
+            title: title.to_string(),
+            visit_count,
+            last_visit: NOW - days_ago * DAY,
+        }
+    }
+
+    fn bookmark(url: &str, title: &str) -> Bookmark {
+        Bookmark { id: 1, folder: None, title: title.to_string(), url: url.to_string(), added: NOW }
+    }
+
+    #[test]
+    fn bookmark_beats_old_history() {
+        let history = [visit("https://example.com/news", "Old news", 1, 200)];
+        let bookmarks = [bookmark("https://example.org/news", "Bookmarked news")];
+        let ranked = rank("news", &history, &bookmarks, &[], NOW);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].url, "https://example.org/news");
+        assert_eq!(ranked[0].kind, SuggestionKind::Bookmark);
+    }
+
+    #[test]
+    fn open_tab_wins_kind_on_merge() {
+        let url = "https://example.com/docs";
+        let history = [visit(url, "Docs", 5, 1)];
+        let bookmarks = [bookmark(url, "Docs")];
+        let tabs = [(url.to_string(), "Docs".to_string())];
+        let ranked = rank("docs", &history, &bookmarks, &tabs, NOW);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].kind, SuggestionKind::OpenTab);
+
+        let alone = rank("docs", &history, &[], &[], NOW);
+        assert!(ranked[0].score > alone[0].score);
+    }
+
+    #[test]
+    fn missing_word_excludes_page() {
+        let history = [visit("https://www.rust-lang.org/", "Rust", 10, 0)];
+        assert!(rank("rust docs", &history, &[], &[], NOW).is_empty());
+        assert_eq!(rank("rust lang", &history, &[], &[], NOW).len(), 1);
+    }
+
+    #[test]
+    fn host_prefix_outranks_title_match() {
+        let history = [
+            visit("https://example.com/tips", "Git tips", 3, 1),
+            visit("https://www.github.com/", "Home", 3, 1),
+        ];
+        let ranked = rank("git", &history, &[], &[], NOW);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].url, "https://www.github.com/");
+        assert!(ranked[0].score > ranked[1].score);
+    }
+}
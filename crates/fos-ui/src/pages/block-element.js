@@ -0,0 +1,43 @@
+// Block Element: build a CSS selector for the element the context menu was
+// opened on (remembered by the internal world's contextmenu listener and
+// claimed when the menu opened), hide every match right away and return
+// the selector ('' if there is nothing to hide).
+(function () {
+    'use strict';
+
+    // Ids and classes with long digit runs are usually generated per page load
+    const GENERATED = /\d{3,}/;
+
+    const target = window.fosMenuTarget;
+    if (!target || !target.isConnected || target.nodeType !== 1) {
+        return '';
+    }
+
+    const path = [];
+    for (let el = target; el && el !== document.body && el !== document.documentElement; el = el.parentElement) {
+        if (el.id && !GENERATED.test(el.id)) {
+            path.unshift('#' + CSS.escape(el.id));
+            break;
+        }
+        let part = el.localName;
+        const classes = [...el.classList].filter(c => !GENERATED.test(c)).slice(0, 2);
+        if (classes.length) {
+            part += '.' + classes.map(c => CSS.escape(c)).join('.');
+        }
+        const parent = el.parentElement;
+        if (parent && [...parent.children].filter(s => s.matches(part)).length > 1) {
+            part += ':nth-child(' + ([...parent.children].indexOf(el) + 1) + ')';
+        }
+        path.unshift(part);
+        if (document.querySelectorAll(path.join(' > ')).length === 1) {
+            break;
+        }
+    }
+
+    const selector = path.join(' > ');
+    if (!selector) {
+        return '';
+    }
+    document.querySelectorAll(selector).forEach(el => el.style.setProperty('display', 'none', 'important'));
+    return selector;
+})();
@@ -0,0 +1,8 @@
+// fos://bookmarks - move a bookmark as soon as a folder is picked
+(function() {
+    'use strict';
+
+    document.querySelectorAll('select[data-autosubmit]').forEach(select => {
+        select.addEventListener('change', () => select.form.submit());
+    });
+})();
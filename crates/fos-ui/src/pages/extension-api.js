@@ -0,0 +1,34 @@
+// Extension API: the small part of the WebExtensions API that content
+// scripts get (runtime.id and storage.local), as `browser` and `chrome`.
+// Runs in the extension's own JavaScript world, before its scripts.
+(function () {
+    'use strict';
+
+    const handler = window.webkit.messageHandlers[__FOS_HANDLER__];
+    const call = (op, arg) => handler.postMessage(JSON.stringify({ op, arg: arg === undefined ? null : arg }))
+        .then(reply => JSON.parse(reply));
+
+    // Promise style (browser.*), or callback style (chrome.*) when a callback is given
+    const api = (promise, callback) => {
+        if (typeof callback === 'function') {
+            promise.then(callback, () => callback());
+            return undefined;
+        }
+        return promise;
+    };
+
+    const local = {
+        get: (keys, callback) => api(call('get', typeof keys === 'function' ? null : keys),
+            typeof keys === 'function' ? keys : callback),
+        set: (items, callback) => api(call('set', items).then(() => undefined), callback),
+        remove: (keys, callback) => api(call('remove', keys).then(() => undefined), callback),
+        clear: (callback) => api(call('clear', null).then(() => undefined), callback),
+    };
+
+    const extension = Object.freeze({
+        runtime: Object.freeze({ id: __FOS_ID__ }),
+        storage: Object.freeze({ local: Object.freeze(local) }),
+    });
+    window.browser = extension;
+    window.chrome = extension;
+})();
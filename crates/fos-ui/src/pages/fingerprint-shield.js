@@ -0,0 +1,91 @@
+// Fingerprinting protection: runs in the page's own world before its
+// scripts and flattens or perturbs high-entropy APIs. Canvas noise is
+// seeded per browser session and site, so a site sees a stable value for
+// the session but cannot link it to other sites or later sessions.
+// navigator.userAgent/platform are left alone to match the UA settings.
+(function () {
+    'use strict';
+
+    const SALT = __FOS_SALT__;
+
+    let seed = SALT;
+    for (const c of location.hostname) {
+        seed = Math.imul(seed ^ c.charCodeAt(0), 16777619) >>> 0;
+    }
+
+    // Replace a prototype getter, keeping its enumerability
+    function define(proto, name, get) {
+        const desc = Object.getOwnPropertyDescriptor(proto, name);
+        if (desc && desc.configurable) {
+            Object.defineProperty(proto, name, { get, configurable: true, enumerable: desc.enumerable });
+        }
+    }
+
+    // Hardware and screen: report common values
+    define(Navigator.prototype, 'hardwareConcurrency', () => 4);
+    const round = v => Math.max(100, Math.round(v / 100) * 100);
+    const width = round(screen.width);
+    const height = round(screen.height);
+    define(Screen.prototype, 'width', () => width);
+    define(Screen.prototype, 'height', () => height);
+    define(Screen.prototype, 'availWidth', () => width);
+    define(Screen.prototype, 'availHeight', () => height);
+    define(Screen.prototype, 'colorDepth', () => 24);
+    define(Screen.prototype, 'pixelDepth', () => 24);
+
+    // Canvas readback: flip the low bit of a few channels
+    const getImageData = CanvasRenderingContext2D.prototype.getImageData;
+    function perturb(image) {
+        const data = image.data;
+        let s = seed;
+        for (let i = 0; i < data.length; i += 4) {
+            s = (Math.imul(s, 1103515245) + 12345) >>> 0;
+            if ((s >>> 16) % 16 === 0) {
+                data[i + (s % 3)] ^= 1;
+            }
+        }
+        return image;
+    }
+    function noisyCopy(canvas) {
+        const copy = document.createElement('canvas');
+        copy.width = canvas.width;
+        copy.height = canvas.height;
+        if (!copy.width || !copy.height || copy.width * copy.height > 4000000) {
+            return canvas;
+        }
+        const ctx = copy.getContext('2d');
+        ctx.drawImage(canvas, 0, 0);
+        ctx.putImageData(perturb(getImageData.call(ctx, 0, 0, copy.width, copy.height)), 0, 0);
+        return copy;
+    }
+    CanvasRenderingContext2D.prototype.getImageData = function (...args) {
+        return perturb(getImageData.apply(this, args));
+    };
+    const toDataURL = HTMLCanvasElement.prototype.toDataURL;
+    HTMLCanvasElement.prototype.toDataURL = function (...args) {
+        return toDataURL.apply(noisyCopy(this), args);
+    };
+    const toBlob = HTMLCanvasElement.prototype.toBlob;
+    HTMLCanvasElement.prototype.toBlob = function (...args) {
+        return toBlob.apply(noisyCopy(this), args);
+    };
+
+    // WebGL: hide the GPU vendor and model
+    const UNMASKED_VENDOR = 0x9245;
+    const UNMASKED_RENDERER = 0x9246;
+    for (const gl of [window.WebGLRenderingContext, window.WebGL2RenderingContext]) {
+        if (!gl) {
+            continue;
+        }
+        const getParameter = gl.prototype.getParameter;
+        gl.prototype.getParameter = function (name) {
+            if (name === UNMASKED_VENDOR) {
+                return 'WebKit';
+            }
+            if (name === UNMASKED_RENDERER) {
+                return 'WebKit WebGL';
+            }
+            return getParameter.call(this, name);
+        };
+    }
+})();
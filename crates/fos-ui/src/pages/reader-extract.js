@@ -0,0 +1,71 @@
+// Reader mode: find the main article of the current page and return it
+// as plain text blocks (JSON). Runs in the page; never modifies it.
+(function () {
+    'use strict';
+
+    const SKIP = 'script, style, noscript, iframe, nav, header, footer, aside, form, button, svg, ' +
+        '[role="navigation"], [role="banner"], [role="complementary"], [aria-hidden="true"]';
+    const BLOCKS = 'h1, h2, h3, h4, h5, h6, p, li, pre, blockquote';
+    const NEGATIVE = /comment|sidebar|footer|promo|related|share|social|sponsor|advert|cookie|banner|popup|newsletter|menu/i;
+    const POSITIVE = /article|content|entry|main|post|story|text|body/i;
+
+    function linkDensity(el) {
+        const text = el.textContent.length || 1;
+        let links = 0;
+        el.querySelectorAll('a').forEach(a => { links += a.textContent.length; });
+        return links / text;
+    }
+
+    function score(el) {
+        let s = 0;
+        el.querySelectorAll('p').forEach(p => {
+            const text = p.textContent.trim();
+            if (text.length > 40) {
+                s += 1 + Math.min(text.length / 100, 3) + text.split(',').length - 1;
+            }
+        });
+        const names = (typeof el.className === 'string' ? el.className : '') + ' ' + el.id;
+        if (POSITIVE.test(names)) s *= 1.25;
+        if (NEGATIVE.test(names)) s *= 0.3;
+        return s * (1 - linkDensity(el));
+    }
+
+    // Candidates: semantic containers plus every paragraph's parent and grandparent
+    const candidates = new Set(document.querySelectorAll('article, main, [role="main"]'));
+    document.querySelectorAll('p').forEach(p => {
+        if (p.parentElement) candidates.add(p.parentElement);
+        if (p.parentElement && p.parentElement.parentElement) candidates.add(p.parentElement.parentElement);
+    });
+
+    let best = null;
+    let bestScore = 0;
+    candidates.forEach(el => {
+        if (el.closest(SKIP)) return;
+        const s = score(el);
+        if (s > bestScore) {
+            best = el;
+            bestScore = s;
+        }
+    });
+
+    const blocks = [];
+    if (best) {
+        best.querySelectorAll(BLOCKS).forEach(el => {
+            // Leaf blocks only, outside page chrome and boilerplate
+            if (el.closest(SKIP) || el.querySelector('p, li, pre, blockquote')) return;
+            const names = (typeof el.className === 'string' ? el.className : '') + ' ' + el.id;
+            if (NEGATIVE.test(names) || linkDensity(el) > 0.5) return;
+            const text = el.tagName === 'PRE' ? el.textContent : el.textContent.replace(/\s+/g, ' ').trim();
+            if (text) blocks.push({ tag: el.tagName.toLowerCase(), text: text });
+        });
+    }
+
+    const byline = document.querySelector('[rel="author"], .author, .byline, [itemprop="author"]');
+    return JSON.stringify({
+        title: document.title,
+        byline: byline ? byline.textContent.replace(/\s+/g, ' ').trim() : '',
+        site: location.hostname,
+        url: location.href,
+        blocks: blocks,
+    });
+})();
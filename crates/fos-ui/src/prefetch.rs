@@ -0,0 +1,72 @@
+//! Prefetch Module - Speculative DNS resolution
+//!
+//! Resolves a destination's host before the user commits to it: when a
+//! link has been hovered for `HOVER_DELAY`, or when the omnibox's top
+//! suggestion clearly beats the others. The navigation then skips the DNS
+//! lookup. Hosts resolved recently are not asked for again.
+//!
+//! Pages are not prerendered in hidden web views: each one would cost a
+//! whole web process worth of memory, against the browser's RAM budget.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tracing::debug;
+use webkit6::glib::{Uri, UriFlags};
+use webkit6::NetworkSession;
+
+use crate::omnibox::{Suggestion, SuggestionKind};
+
+/// How long a link must be hovered before its host is resolved
+pub const HOVER_DELAY: Duration = Duration::from_millis(200);
+
+/// Hosts remembered as already resolved
+const MAX_RECENT_HOSTS: usize = 64;
+
+/// How far the top omnibox suggestion must outscore the next one
+const PREDICTION_MARGIN: f64 = 2.0;
+
+// Thread-local recent hosts (since we're running single-threaded GTK)
+thread_local! {
+    static RECENT: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Resolve the host of a URL ahead of a likely navigation
+pub fn prefetch(session: &NetworkSession, url: &str) {
+    if !url.starts_with("http") || !crate::config::with_config(|c| c.dns_prefetch) {
+        return;
+    }
+    // The real host: omnibox::host_of is for display (drops www., keeps the port)
+    let Some(host) = Uri::parse(url, UriFlags::NONE).ok()
+        .and_then(|uri| uri.host())
+        .map(|host| host.to_lowercase())
+        .filter(|host| !host.is_empty())
+    else {
+        return;
+    };
+    let fresh = RECENT.with(|recent| {
+        let mut recent = recent.borrow_mut();
+        if recent.contains(&host) {
+            return false;
+        }
+        if recent.len() == MAX_RECENT_HOSTS {
+            recent.pop_front();
+        }
+        recent.push_back(host.clone());
+        true
+    });
+    if fresh {
+        debug!("Prefetching DNS for {}", host);
+        session.prefetch_dns(&host);
+    }
+}
+
+/// The omnibox suggestion the user will most likely pick, if one clearly
+/// wins (open tabs are switched to, not loaded, so they never count)
+pub fn predicted(suggestions: &[Suggestion]) -> Option<&Suggestion> {
+    let top = suggestions.first().filter(|s| s.kind != SuggestionKind::OpenTab)?;
+    match suggestions.get(1) {
+        Some(next) if top.score < next.score * PREDICTION_MARGIN => None,
+        _ => Some(top),
+    }
+}
@@ -0,0 +1,1087 @@
+//! Internal Pages - fos:// URI scheme
+//!
+//! Serves browser-internal pages straight from Rust through WebKit's
+//! custom URI scheme support:
+//! - fos://bookmarks - bookmark manager with search and folders
+//! - fos://stats - weekly adblock statistics and tracker category toggles
+//! - fos://shield - recent blocks per site, sites with blocking off, blocked elements
+//! - fos://reader/<tab> - reader mode view of a tab's article
+//! - fos://styles - custom CSS per site
+//! - fos://extensions - installed content-script extensions
+//! - fos://settings - browser settings (config.toml), search engines and shortcuts
+//! - fos://metrics - internal metrics in the Prometheus text format
+//! - fos://about - diagnostics: process memory, tabs, blocking, versions
+//!
+//! Hardening:
+//! - Every page is served with a strict Content-Security-Policy
+//! - Scripts live in embedded files and only run with a per-response nonce
+//! - The scheme is local, so web pages cannot link to or frame it
+//! - Actions (delete, move, ...) need a per-session token that only fos://
+//!   pages and the address bar add, and are refused from web pages
+
+use webkit6::{URISchemeRequest, URISchemeResponse, WebContext};
+use webkit6::soup::{MessageHeaders, MessageHeadersType};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+use crate::adblocker::{with_blocking_prefs, TrackerCategory};
+use crate::blockstats::{format_bytes, with_stats};
+use crate::bookmarks::{with_bookmarks, BookmarkStore};
+use crate::config::{encode_query, with_config};
+use crate::extensions::with_extensions;
+use crate::history::now_secs;
+use crate::keymap::{Action, BindError};
+use crate::search::with_search_engines;
+use crate::user_styles::with_user_styles;
+use crate::reader::{render_article, with_reader, MAX_FONT_SIZE, MIN_FONT_SIZE};
+
+/// Scheme used for internal pages
+pub const SCHEME: &str = "fos";
+
+/// Shared stylesheet for internal pages
+const PAGE_CSS: &str = r#"
+    body { font-family: sans-serif; max-width: 760px; margin: 32px auto; padding: 0 16px; }
+    h1 { font-size: 1.4em; }
+    ul { list-style: none; padding-left: 18px; }
+    li { margin: 4px 0; }
+    a { color: #3584e4; text-decoration: none; }
+    .muted { color: #888; font-size: 0.85em; }
+    .folder { font-weight: bold; margin-top: 10px; }
+    .big { font-size: 2em; font-weight: bold; }
+    table { border-collapse: collapse; }
+    td { padding: 2px 12px 2px 0; }
+    meter { width: 300px; }
+    form { display: inline; }
+    input, select, button { font: inherit; }
+    .reader { font-family: serif; line-height: 1.6; padding: 8px 24px 24px; border-radius: 6px; }
+    .reader ul { list-style: disc; }
+    .reader pre { white-space: pre-wrap; font-size: 0.85em; }
+    .reader blockquote { border-left: 3px solid #888; margin-left: 0; padding-left: 12px; }
+    .reader.light { background: #fbfaf7; color: #222; }
+    .reader.dark { background: #1b1b1d; color: #d8d8d8; }
+    .reader.size-1 { font-size: 15px; }
+    .reader.size-2 { font-size: 17px; }
+    .reader.size-3 { font-size: 19px; }
+    .reader.size-4 { font-size: 22px; }
+    .reader.size-5 { font-size: 26px; }
+    .reader-bar { text-align: right; margin-bottom: 8px; }
+    textarea { width: 100%; font-family: monospace; font-size: 0.9em; }
+    .style { margin: 16px 0; }
+    label { display: block; margin: 6px 0; }
+    @media (prefers-color-scheme: dark) {
+        body { background: #1e1e1e; color: #ddd; }
+        a { color: #78aeed; }
+    }
+"#;
+
+/// Script for fos://bookmarks
+const BOOKMARKS_JS: &str = include_str!("pages/bookmarks.js");
+
+/// What a page handler produces
+pub(crate) enum PageResponse {
+    /// A rendered page, optionally with one embedded script
+    Page {
+        title: String,
+        body: String,
+        script: Option<&'static str>,
+    },
+    /// Navigate elsewhere (used after actions)
+    Redirect(String),
+    /// Plain text (e.g. metrics for tools)
+    Text(String),
+}
+
+impl PageResponse {
+    fn page(title: &str, body: String) -> Self {
+        Self::Page { title: title.to_string(), body, script: None }
+    }
+}
+
+/// A parsed fos:// URI: `fos://<page>/<action>?<query>`
+pub(crate) struct FosUri {
+    pub page: String,
+    pub action: String,
+    pub query: Vec<(String, String)>,
+}
+
+impl FosUri {
+    /// Get a query parameter by name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query.iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Get a numeric query parameter by name
+    pub fn param_u64(&self, name: &str) -> Option<u64> {
+        self.param(name).and_then(|v| v.parse().ok())
+    }
+}
+
+/// Parse a fos:// URI into page, action and query parameters
+pub(crate) fn parse_fos_uri(uri: &str) -> FosUri {
+    let rest = uri.strip_prefix("fos://").unwrap_or(uri);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let path = path.trim_end_matches('/');
+    let (page, action) = path.split_once('/').unwrap_or((path, ""));
+
+    let query = query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(k), percent_decode(v))
+        })
+        .collect();
+
+    FosUri {
+        page: page.to_lowercase(),
+        action: action.to_string(),
+        query,
+    }
+}
+
+/// Decode a percent-encoded query component (`+` means space)
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 3 <= bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                out.push(u8::from_str_radix(hex, 16).unwrap_or(b'%'));
+                i += 2;
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Escape text for safe inclusion in HTML
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Render a response into a full HTML document
+fn render(response: &PageResponse, nonce: &str) -> String {
+    match response {
+        PageResponse::Page { title, body, script } => {
+            let script = script
+                .map(|js| format!("<script nonce=\"{}\">{}</script>", nonce, js))
+                .unwrap_or_default();
+            format!(
+                "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title>\
+                 <style nonce=\"{}\">{}</style></head><body>{}{}</body></html>",
+                escape_html(title), nonce, PAGE_CSS, body, script
+            )
+        }
+        PageResponse::Text(text) => text.clone(),
+        PageResponse::Redirect(to) => format!(
+            "<!DOCTYPE html><html><head><meta http-equiv=\"refresh\" content=\"0; url={}\"></head></html>",
+            escape_html(to)
+        ),
+    }
+}
+
+/// Content-Security-Policy for internal pages: nothing runs or loads
+/// unless it carries this response's nonce or comes from fos:// itself
+fn content_security_policy(nonce: &str) -> String {
+    format!(
+        "default-src 'none'; script-src 'nonce-{nonce}'; style-src 'nonce-{nonce}'; \
+         img-src fos: data:; form-action fos:; base-uri 'none'; frame-ancestors 'none'"
+    )
+}
+
+/// Register the fos:// scheme on a web context
+pub fn register(context: &WebContext) {
+    context.register_uri_scheme(SCHEME, handle_request);
+
+    // Local: non-local (web) pages cannot link to, frame, or read fos:// pages
+    if let Some(security) = context.security_manager() {
+        security.register_uri_scheme_as_local(SCHEME);
+        security.register_uri_scheme_as_secure(SCHEME);
+    }
+    info!("Registered {}:// internal pages", SCHEME);
+}
+
+/// Token that action URLs must carry, new for every browser session
+fn action_token() -> &'static str {
+    static TOKEN: OnceLock<String> = OnceLock::new();
+    TOKEN.get_or_init(|| webkit6::glib::uuid_string_random().replace('-', ""))
+}
+
+/// URL of an action with the session token, e.g. `action_url("bookmarks/delete")`
+/// (append parameters with `&amp;`)
+fn action_url(path: &str) -> String {
+    format!("fos://{}?token={}", path, action_token())
+}
+
+/// Hidden field with the session token, for forms that submit to an action
+fn token_field() -> String {
+    format!("<input type=\"hidden\" name=\"token\" value=\"{}\">", action_token())
+}
+
+/// Add the session token to a fos:// action URL typed into the address bar
+pub(crate) fn typed_uri(uri: &str) -> String {
+    if !uri.starts_with("fos://") || parse_fos_uri(uri).action.is_empty() {
+        return uri.to_string();
+    }
+    // First, so a stale token in the typed URL is not the one read
+    match uri.split_once('?') {
+        Some((path, query)) => format!("{}?token={}&{}", path, action_token(), query),
+        None => format!("{}?token={}", uri, action_token()),
+    }
+}
+
+/// Actions need the session token, which only fos:// pages and the address
+/// bar know, and must not come from a web page
+fn is_trusted_request(request: &URISchemeRequest, uri: &FosUri) -> bool {
+    if uri.param("token") != Some(action_token()) {
+        return false;
+    }
+    let referer = request.http_headers().and_then(|h| h.one("Referer"));
+    referer.is_none_or(|referer| referer.starts_with("fos://"))
+}
+
+fn handle_request(request: &URISchemeRequest) {
+    let uri = request.uri().map(|u| u.to_string()).unwrap_or_default();
+    let mut fos_uri = parse_fos_uri(&uri);
+
+    // fos://reader/<tab> names a tab, not an action
+    if !fos_uri.action.is_empty() && fos_uri.page != "reader" && !is_trusted_request(request, &fos_uri) {
+        warn!("Refused {} without a valid token", fos_uri.page);
+        fos_uri.action.clear();
+    }
+
+    let response = match fos_uri.page.as_str() {
+        "bookmarks" => bookmarks_page(&fos_uri),
+        "stats" => stats_page(&fos_uri),
+        "reader" => reader_page(&fos_uri),
+        "styles" => styles_page(&fos_uri),
+        "extensions" => extensions_page(&fos_uri),
+        "settings" => settings_page(&fos_uri),
+        "metrics" => metrics_page(),
+        "about" => about_page(),
+        "shield" => shield_page(&fos_uri),
+        _ => PageResponse::page("Not Found", format!(
+            "<h1>Not Found</h1><p>No internal page at <code>{}</code></p>",
+            escape_html(&uri)
+        )),
+    };
+
+    let nonce = webkit6::glib::uuid_string_random().replace('-', "");
+    let html = render(&response, &nonce);
+
+    let bytes = webkit6::glib::Bytes::from_owned(html.into_bytes());
+    let stream = webkit6::gio::MemoryInputStream::from_bytes(&bytes);
+    let scheme_response = URISchemeResponse::new(&stream, bytes.len() as i64);
+    scheme_response.set_content_type(match response {
+        PageResponse::Text(_) => "text/plain; charset=utf-8",
+        _ => "text/html",
+    });
+
+    let headers = MessageHeaders::new(MessageHeadersType::Response);
+    headers.append("Content-Security-Policy", &content_security_policy(&nonce));
+    headers.append("X-Content-Type-Options", "nosniff");
+    headers.append("Referrer-Policy", "same-origin");
+    scheme_response.set_http_headers(headers);
+
+    request.finish_with_response(&scheme_response);
+}
+
+/// fos://bookmarks - list, search and organise bookmarks
+fn bookmarks_page(uri: &FosUri) -> PageResponse {
+    match uri.action.as_str() {
+        "delete" => {
+            let removed = uri.param_u64("id").and_then(|id| with_bookmarks(|store| {
+                let removed = store.remove(id);
+                store.save();
+                removed
+            }));
+            if let Some(bookmark) = removed {
+                crate::undo::register("Bookmark deleted", move || with_bookmarks(|store| {
+                    store.restore(Vec::new(), vec![bookmark]);
+                    store.save();
+                }));
+            }
+            return PageResponse::Redirect("fos://bookmarks".to_string());
+        }
+        "delete-folder" => {
+            if let Some(id) = uri.param_u64("id") {
+                let (folders, bookmarks) = with_bookmarks(|store| {
+                    let removed = store.remove_folder(id);
+                    store.save();
+                    removed
+                });
+                crate::undo::register("Folder deleted", move || with_bookmarks(|store| {
+                    store.restore(folders, bookmarks);
+                    store.save();
+                }));
+            }
+            return PageResponse::Redirect("fos://bookmarks".to_string());
+        }
+        "new-folder" => {
+            if let Some(name) = uri.param("name").filter(|n| !n.trim().is_empty()) {
+                with_bookmarks(|store| {
+                    store.create_folder(name.trim(), None);
+                    store.save();
+                });
+            }
+            return PageResponse::Redirect("fos://bookmarks".to_string());
+        }
+        "move" => {
+            if let Some(id) = uri.param_u64("id") {
+                let folder = uri.param_u64("folder");
+                with_bookmarks(|store| {
+                    store.move_to(id, folder);
+                    store.save();
+                });
+            }
+            return PageResponse::Redirect("fos://bookmarks".to_string());
+        }
+        _ => {}
+    }
+
+    let query = uri.param("q").unwrap_or("").to_string();
+    let mut body = String::from("<h1>Bookmarks</h1>");
+    body.push_str(&format!(
+        "<p><form action=\"fos://bookmarks\"><input name=\"q\" placeholder=\"Search bookmarks\" value=\"{}\"> \
+         <button>Search</button></form> \
+         <form action=\"fos://bookmarks/new-folder\">{}<input name=\"name\" placeholder=\"New folder\"> \
+         <button>Create</button></form></p>",
+        escape_html(&query),
+        token_field(),
+    ));
+
+    with_bookmarks(|store| {
+        if query.trim().is_empty() {
+            body.push_str("<ul>");
+            render_folder(store, None, &mut body);
+            body.push_str("</ul>");
+        } else {
+            let results = store.search(&query);
+            body.push_str(&format!("<p class=\"muted\">{} results</p><ul>", results.len()));
+            for b in results {
+                render_bookmark(store, b, &mut body);
+            }
+            body.push_str("</ul>");
+        }
+    });
+
+    PageResponse::Page {
+        title: "Bookmarks".to_string(),
+        body,
+        script: Some(BOOKMARKS_JS),
+    }
+}
+
+fn render_folder(store: &BookmarkStore, folder: Option<u64>, body: &mut String) {
+    for f in store.folders_in(folder) {
+        body.push_str(&format!(
+            "<li><div class=\"folder\">📁 {} <a class=\"muted\" href=\"{}&amp;id={}\">delete</a></div><ul>",
+            escape_html(&f.name), action_url("bookmarks/delete-folder"), f.id
+        ));
+        render_folder(store, Some(f.id), body);
+        body.push_str("</ul></li>");
+    }
+
+    for b in store.bookmarks_in(folder) {
+        render_bookmark(store, b, body);
+    }
+}
+
+fn render_bookmark(store: &BookmarkStore, b: &crate::bookmarks::Bookmark, body: &mut String) {
+    let mut options = String::from("<option value=\"\">(root)</option>");
+    for f in store.all_folders() {
+        let selected = if b.folder == Some(f.id) { " selected" } else { "" };
+        options.push_str(&format!(
+            "<option value=\"{}\"{}>{}</option>",
+            f.id, selected, escape_html(&f.name)
+        ));
+    }
+
+    body.push_str(&format!(
+        "<li><a href=\"{url}\">{title}</a> <span class=\"muted\">{url_text}</span> \
+         <form action=\"fos://bookmarks/move\">{token}<input type=\"hidden\" name=\"id\" value=\"{id}\">\
+         <select name=\"folder\" data-autosubmit>{options}</select></form> \
+         <a class=\"muted\" href=\"{delete}&amp;id={id}\">delete</a></li>",
+        url = escape_html(&b.url),
+        title = escape_html(&b.title),
+        url_text = escape_html(&b.url),
+        id = b.id,
+        options = options,
+        token = token_field(),
+        delete = action_url("bookmarks/delete"),
+    ));
+}
+
+/// fos://stats - what the adblocker did over the last week
+fn stats_page(uri: &FosUri) -> PageResponse {
+    if uri.action == "toggle" {
+        if let Some(category) = uri.param("category").and_then(TrackerCategory::from_name) {
+            with_blocking_prefs(|prefs| {
+                prefs.set_enabled(category, !prefs.is_enabled(category));
+                prefs.save();
+            });
+        }
+        return PageResponse::Redirect("fos://stats".to_string());
+    }
+
+    let report = with_stats(|stats| stats.report(7, now_secs()));
+    let max_day = report.per_day.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+
+    let mut body = String::from("<h1>Blocking Statistics</h1>");
+    body.push_str(&format!(
+        "<p><span class=\"big\">{}</span> requests blocked in the last {} days, \
+         saving about <b>{}</b>.</p><p class=\"muted\">Computed locally, never uploaded.</p>",
+        report.blocked, report.days, format_bytes(report.bytes_saved)
+    ));
+
+    body.push_str("<h2>Per day</h2><table>");
+    // <meter> instead of inline widths: the CSP forbids style attributes
+    for (day, blocked) in &report.per_day {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td><meter max=\"{}\" value=\"{}\"></meter></td></tr>",
+            day, blocked, max_day, blocked
+        ));
+    }
+    body.push_str("</table>");
+
+    body.push_str("<h2>Categories</h2><table>");
+    for (category, blocked) in &report.per_category {
+        let enabled = with_blocking_prefs(|p| p.is_enabled(*category));
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td><a href=\"{}&amp;category={}\">{}</a></td></tr>",
+            category.label(),
+            blocked,
+            if enabled { "Blocked" } else { "<span class=\"muted\">Allowed</span>" },
+            action_url("stats/toggle"),
+            category.name(),
+            if enabled { "Allow" } else { "Block" },
+        ));
+    }
+    body.push_str("</table>");
+
+    body.push_str("<h2>Top blocked domains</h2><table>");
+    for (domain, count) in &report.top_domains {
+        body.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", count, escape_html(domain)));
+    }
+    body.push_str("</table><h2>Top offending sites</h2><table>");
+    for (site, count) in &report.top_sites {
+        body.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>", count, escape_html(site)));
+    }
+    body.push_str("</table>");
+
+    PageResponse::page("Statistics", body)
+}
+
+/// fos://reader/<tab> - the article extracted from a tab, text only
+fn reader_page(uri: &FosUri) -> PageResponse {
+    let Ok(tab) = uri.action.parse::<u64>() else {
+        return PageResponse::page("Reader", "<h1>Reader</h1><p>Press F9 on a page to open it in reader mode.</p>".to_string());
+    };
+
+    with_reader(|reader| {
+        if let Some(size) = uri.param_u64("size") {
+            let size = size.clamp(u64::from(MIN_FONT_SIZE), u64::from(MAX_FONT_SIZE));
+            reader.font_size = u8::try_from(size).unwrap_or(MAX_FONT_SIZE);
+        }
+        match uri.param("theme") {
+            Some("dark") => reader.dark = true,
+            Some("light") => reader.dark = false,
+            _ => {}
+        }
+
+        match reader.article(tab) {
+            Some(article) => PageResponse::page(
+                &article.title,
+                render_article(article, tab, reader.font_size, reader.dark),
+            ),
+            None => PageResponse::page("Reader", "<h1>Reader</h1><p>This article is no longer available. \
+                Go back and press F9 again.</p>".to_string()),
+        }
+    })
+}
+
+/// fos://styles - add, edit, disable and delete user styles
+fn styles_page(uri: &FosUri) -> PageResponse {
+    let id = uri.param_u64("id");
+    let domain = uri.param("domain").unwrap_or("");
+    let css = uri.param("css").unwrap_or("");
+    let changed = match (uri.action.as_str(), id) {
+        ("add", _) => with_user_styles(|store| store.add(domain, css)).is_some(),
+        ("save", Some(id)) => {
+            with_user_styles(|store| store.update(id, domain, css));
+            true
+        }
+        ("toggle", Some(id)) => {
+            with_user_styles(|store| {
+                let enabled = store.styles().iter().any(|s| s.id == id && s.enabled);
+                store.set_enabled(id, !enabled);
+            });
+            true
+        }
+        ("delete", Some(id)) => {
+            if let Some(style) = with_user_styles(|store| store.remove(id)) {
+                crate::undo::register("Style deleted", move || {
+                    with_user_styles(|store| {
+                        store.restore(style);
+                        store.save();
+                    });
+                    crate::user_styles::refresh();
+                });
+            }
+            true
+        }
+        _ => false,
+    };
+    if changed {
+        with_user_styles(|store| store.save());
+        crate::user_styles::refresh();
+        return PageResponse::Redirect("fos://styles".to_string());
+    }
+
+    let mut body = String::from(
+        "<h1>User Styles</h1><p class=\"muted\">Custom CSS for a site and its subdomains.</p>"
+    );
+    with_user_styles(|store| {
+        for style in store.styles() {
+            body.push_str(&format!(
+                "<div class=\"style\"><form action=\"fos://styles/save\">{token}\
+                 <input type=\"hidden\" name=\"id\" value=\"{id}\">\
+                 <input name=\"domain\" value=\"{domain}\"> <button>Save</button> \
+                 <textarea name=\"css\" rows=\"6\">{css}</textarea></form> \
+                 <a href=\"{toggle_url}&amp;id={id}\">{toggle}</a> \
+                 <a class=\"muted\" href=\"{delete_url}&amp;id={id}\">delete</a>{state}</div>",
+                id = style.id,
+                token = token_field(),
+                toggle_url = action_url("styles/toggle"),
+                delete_url = action_url("styles/delete"),
+                domain = escape_html(&style.domain),
+                css = escape_html(&style.css),
+                toggle = if style.enabled { "Disable" } else { "Enable" },
+                state = if style.enabled { "" } else { " <span class=\"muted\">(disabled)</span>" },
+            ));
+        }
+    });
+
+    body.push_str(&format!(
+        "<h2>New style</h2><div class=\"style\"><form action=\"fos://styles/add\">{}\
+         <input name=\"domain\" placeholder=\"example.com\"> <button>Add</button> \
+         <textarea name=\"css\" rows=\"6\" placeholder=\"body {{ background: #111 !important; color: #ddd !important; }}\"></textarea>\
+         </form></div>",
+        token_field()
+    ));
+
+    PageResponse::page("User Styles", body)
+}
+
+/// fos://extensions - installed extensions
+fn extensions_page(uri: &FosUri) -> PageResponse {
+    let id = uri.param("id").unwrap_or("");
+    match uri.action.as_str() {
+        "enable" | "disable" => {
+            with_extensions(|store| {
+                store.set_enabled(id, uri.action == "enable");
+                store.save();
+            });
+            crate::extensions::refresh();
+            return PageResponse::Redirect("fos://extensions".to_string());
+        }
+        "reload" => {
+            crate::extensions::reload();
+            return PageResponse::Redirect("fos://extensions".to_string());
+        }
+        _ => {}
+    }
+
+    let mut body = format!(
+        "<h1>Extensions</h1><p class=\"muted\">Folders with a manifest.json in <code>{}</code>. \
+         Content scripts and storage.local are supported; changes apply to pages loaded afterwards. \
+         <a href=\"{}\">Reload</a></p>",
+        escape_html(&crate::extensions::extensions_dir().display().to_string()),
+        action_url("extensions/reload")
+    );
+    with_extensions(|store| {
+        if store.extensions().is_empty() {
+            body.push_str("<p class=\"muted\">No extensions installed.</p>");
+        }
+        for extension in store.extensions() {
+            let manifest = &extension.manifest;
+            let mut matches: Vec<&str> = manifest.content_scripts.iter()
+                .flat_map(|cs| cs.matches.iter().map(String::as_str))
+                .collect();
+            matches.dedup();
+            body.push_str(&format!(
+                "<div class=\"style\"><b>{name}</b> <span class=\"muted\">{version}</span>{state}<br>\
+                 {description}<br><span class=\"muted\">Runs on {matches} &middot; storage {storage}</span><br>\
+                 <a href=\"{action}&amp;id={id}\">{toggle}</a></div>",
+                name = escape_html(&manifest.name),
+                version = escape_html(&manifest.version),
+                state = if extension.enabled { "" } else { " <span class=\"muted\">(disabled)</span>" },
+                description = escape_html(&manifest.description),
+                matches = escape_html(&matches.join(", ")),
+                storage = format_bytes(crate::extensions::storage_bytes(&extension.id)),
+                action = action_url(if extension.enabled { "extensions/disable" } else { "extensions/enable" }),
+                id = encode_query(&extension.id),
+                toggle = if extension.enabled { "Disable" } else { "Enable" },
+            ));
+        }
+        if !store.errors().is_empty() {
+            body.push_str("<h2>Not loaded</h2><ul>");
+            for (folder, error) in store.errors() {
+                body.push_str(&format!(
+                    "<li><code>{}</code> <span class=\"muted\">{}</span></li>",
+                    escape_html(folder), escape_html(error)
+                ));
+            }
+            body.push_str("</ul>");
+        }
+    });
+
+    PageResponse::page("Extensions", body)
+}
+
+/// fos://settings - edit config.toml
+fn settings_page(uri: &FosUri) -> PageResponse {
+    match uri.action.as_str() {
+        "engine-add" => {
+            with_search_engines(|m| {
+                let added = m.add(
+                    uri.param("name").unwrap_or(""),
+                    uri.param("keyword").unwrap_or(""),
+                    uri.param("url").unwrap_or(""),
+                );
+                if added.is_some() {
+                    m.save();
+                }
+            });
+            return PageResponse::Redirect("fos://settings".to_string());
+        }
+        "engine-delete" => {
+            let removed = uri.param("id").and_then(|id| with_search_engines(|m| {
+                let removed = m.remove(id);
+                m.save();
+                removed
+            }));
+            if let Some((pos, engine)) = removed {
+                crate::undo::register("Search engine deleted", move || with_search_engines(|m| {
+                    m.restore(pos, engine);
+                    m.save();
+                }));
+            }
+            return PageResponse::Redirect("fos://settings".to_string());
+        }
+        "ua-site-add" => {
+            if let Some(site) = uri.param("site") {
+                with_config(|config| {
+                    config.user_agent.set_site(site, Some(uri.param("value").unwrap_or("")));
+                    config.save();
+                });
+            }
+            return PageResponse::Redirect("fos://settings".to_string());
+        }
+        "ua-site-remove" => {
+            if let Some(site) = uri.param("site") {
+                with_config(|config| {
+                    config.user_agent.set_site(site, None);
+                    config.save();
+                });
+            }
+            return PageResponse::Redirect("fos://settings".to_string());
+        }
+        "key-set" => {
+            let Some(action) = uri.param("action").and_then(Action::from_id) else {
+                return PageResponse::Redirect("fos://settings".to_string());
+            };
+            let result = with_config(|config| {
+                let result = config.keys.bind(action, uri.param("chord").unwrap_or(""));
+                if result.is_ok() {
+                    config.save();
+                }
+                result
+            });
+            return PageResponse::Redirect(match result {
+                Ok(()) => "fos://settings".to_string(),
+                Err(BindError::Invalid) => format!("fos://settings?key-error={}", action.id()),
+                Err(BindError::Conflict(other)) => {
+                    format!("fos://settings?key-error={}&conflict={}", action.id(), other.id())
+                }
+            });
+        }
+        "key-reset" => {
+            if let Some(action) = uri.param("action").and_then(Action::from_id) {
+                with_config(|config| {
+                    config.keys.reset(action);
+                    config.save();
+                });
+            }
+            return PageResponse::Redirect("fos://settings".to_string());
+        }
+        _ => {}
+    }
+
+    if uri.action == "save" {
+        // Unchecked checkboxes are not submitted at all
+        let checked = |name: &str| uri.param(name).is_some();
+        with_config(|config| {
+            if let Some(homepage) = uri.param("homepage").map(str::trim).filter(|h| !h.is_empty()) {
+                config.homepage = homepage.to_string();
+            }
+            if let Some(engine) = uri.param("search_engine").filter(|e| with_search_engines(|m| m.find(e).is_some())) {
+                config.search_engine = engine.to_string();
+            }
+            config.hardware_acceleration = checked("hardware_acceleration");
+            config.dns_prefetch = checked("dns_prefetch");
+            if let Some(user_agent) = uri.param("user_agent") {
+                config.user_agent.default = user_agent.to_string();
+            }
+            config.adblock.enabled = checked("adblock");
+            config.adblock.cosmetic_filters = checked("cosmetic_filters");
+            config.adblock.youtube_adskip = checked("youtube_adskip");
+            config.fingerprint.enabled = checked("fingerprint");
+            config.save();
+        });
+        return PageResponse::Redirect("fos://settings".to_string());
+    }
+
+    let config = with_config(|c| c.clone());
+    let check = |on: bool| if on { " checked" } else { "" };
+    let mut engines = String::new();
+    let mut engine_rows = String::new();
+    with_search_engines(|m| {
+        let default_id = m.default_engine().id.clone();
+        for engine in m.engines() {
+            let selected = if engine.id == default_id { " selected" } else { "" };
+            engines.push_str(&format!(
+                "<option value=\"{}\"{}>{}</option>",
+                escape_html(&engine.id), selected, escape_html(&engine.name)
+            ));
+            engine_rows.push_str(&format!(
+                "<tr><td>{}</td><td><code>{}</code></td><td class=\"muted\">{}</td>\
+                 <td><a class=\"muted\" href=\"{}&amp;id={}\">delete</a></td></tr>",
+                escape_html(&engine.name),
+                escape_html(&engine.keyword),
+                escape_html(&engine.url),
+                action_url("settings/engine-delete"),
+                escape_html(&engine.id),
+            ));
+        }
+    });
+
+    let mut ua_options = String::new();
+    let mut ua_presets = vec![("", "WebKit's own"), ("desktop", "Desktop (Chrome on Linux)"), ("mobile", "Mobile (Safari on iPhone)")];
+    if ua_presets.iter().all(|(value, _)| *value != config.user_agent.default) {
+        ua_presets.push((config.user_agent.default.as_str(), "Custom (from config.toml)"));
+    }
+    for (value, label) in ua_presets {
+        let selected = if value == config.user_agent.default { " selected" } else { "" };
+        ua_options.push_str(&format!("<option value=\"{}\"{}>{}</option>", escape_html(value), selected, label));
+    }
+    let mut ua_rows = String::new();
+    for (site, value) in &config.user_agent.sites {
+        ua_rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"muted\">{}</td>\
+             <td><a class=\"muted\" href=\"{}&amp;site={}\">remove</a></td></tr>",
+            escape_html(site),
+            escape_html(if value.is_empty() { "WebKit's own" } else { value }),
+            action_url("settings/ua-site-remove"),
+            encode_query(site),
+        ));
+    }
+
+    let mut key_rows = String::new();
+    for action in Action::ALL {
+        key_rows.push_str(&format!(
+            "<tr><td>{}</td><td><form action=\"fos://settings/key-set\">{token}\
+             <input type=\"hidden\" name=\"action\" value=\"{id}\">\
+             <input name=\"chord\" size=\"16\" value=\"{}\" placeholder=\"unbound\"> <button>Set</button></form></td>\
+             <td class=\"muted\">{}</td>\
+             <td><a class=\"muted\" href=\"{reset}&amp;action={id}\">reset</a></td></tr>",
+            action.label(),
+            escape_html(config.keys.binding(action)),
+            action.default_chord(),
+            id = action.id(),
+            token = token_field(),
+            reset = action_url("settings/key-reset"),
+        ));
+    }
+    let mut key_notes = String::new();
+    if let Some(action) = uri.param("key-error").and_then(Action::from_id) {
+        let message = match uri.param("conflict").and_then(Action::from_id) {
+            Some(other) => format!("That shortcut is already used by \"{}\"; \"{}\" was not changed.", other.label(), action.label()),
+            None => format!("Not a shortcut; \"{}\" was not changed. Use e.g. Ctrl+Shift+N, Alt+Left or F9.", action.label()),
+        };
+        key_notes.push_str(&format!("<p><b>{}</b></p>", escape_html(&message)));
+    }
+    for problem in config.keys.problems() {
+        key_notes.push_str(&format!("<p><b>{}</b></p>", escape_html(&problem)));
+    }
+
+    let body = format!(
+        "<h1>Settings</h1><form action=\"fos://settings/save\">{token}\
+         <h2>General</h2>\
+         <label>Homepage <input name=\"homepage\" size=\"40\" value=\"{homepage}\"></label>\
+         <label>Search engine <select name=\"search_engine\">{engines}</select></label>\
+         <label><input type=\"checkbox\" name=\"hardware_acceleration\"{hw}> Hardware acceleration \
+         <span class=\"muted\">(applies to open tabs; may flicker on some GPUs)</span></label>\
+         <label><input type=\"checkbox\" name=\"dns_prefetch\"{prefetch}> Look up hovered links and \
+         predicted addresses ahead of time <span class=\"muted\">(DNS only; pages are not preloaded)</span></label>\
+         <label>User agent <select name=\"user_agent\">{ua_options}</select></label>\
+         <h2>Adblocker</h2>\
+         <label><input type=\"checkbox\" name=\"adblock\"{adblock}> Block ads and trackers</label>\
+         <label><input type=\"checkbox\" name=\"cosmetic_filters\"{cosmetic}> Hide ad placeholders (cosmetic filters)</label>\
+         <label><input type=\"checkbox\" name=\"youtube_adskip\"{youtube}> Skip YouTube ads</label>\
+         <label><input type=\"checkbox\" name=\"fingerprint\"{fingerprint}> Fingerprinting protection \
+         <span class=\"muted\">({exceptions} sites excepted; set per site in the shield panel)</span></label>\
+         <p><button>Save</button></p></form>\
+         <h2>Search engines</h2>\
+         <p class=\"muted\">Type a keyword before a search in the address bar to use another engine, \
+         e.g. <code>w rust</code> or <code>!w rust</code>.</p>\
+         <table><tr><th>Name</th><th>Keyword</th><th>URL</th><th></th></tr>{engine_rows}</table>\
+         <form action=\"fos://settings/engine-add\">{token}\
+         <input name=\"name\" placeholder=\"Name\"> <input name=\"keyword\" size=\"4\" placeholder=\"kw\"> \
+         <input name=\"url\" size=\"40\" placeholder=\"https://example.com/search?q={{searchTerms}}\"> \
+         <button>Add</button></form>\
+         <h2>User agent per site</h2>\
+         <p class=\"muted\">For sites that break with the default; \"Request Mobile Site\" in the page's \
+         context menu adds one too.</p>\
+         <table><tr><th>Site</th><th>User agent</th><th></th></tr>{ua_rows}</table>\
+         <form action=\"fos://settings/ua-site-add\">{token}\
+         <input name=\"site\" placeholder=\"example.com\"> \
+         <input name=\"value\" size=\"40\" placeholder=\"desktop, mobile, or a user agent string\"> \
+         <button>Add</button></form>\
+         <h2 id=\"keys\">Keyboard shortcuts</h2>{key_notes}\
+         <table><tr><th>Action</th><th>Shortcut</th><th>Default</th><th></th></tr>{key_rows}</table>\
+         <p class=\"muted\">Write shortcuts like <code>Ctrl+Shift+N</code> or <code>Alt+Left</code>; \
+         leave one empty to turn it off.</p>\
+         <p class=\"muted\">Stored in config.toml in the data directory; edits to the file apply immediately. \
+         Tracker categories are set at <a href=\"fos://stats\">fos://stats</a>.</p>",
+        homepage = escape_html(&config.homepage),
+        token = token_field(),
+        engines = engines,
+        engine_rows = engine_rows,
+        key_notes = key_notes,
+        ua_options = ua_options,
+        ua_rows = ua_rows,
+        key_rows = key_rows,
+        hw = check(config.hardware_acceleration),
+        prefetch = check(config.dns_prefetch),
+        adblock = check(config.adblock.enabled),
+        cosmetic = check(config.adblock.cosmetic_filters),
+        youtube = check(config.adblock.youtube_adskip),
+        fingerprint = check(config.fingerprint.enabled),
+        exceptions = config.fingerprint.disabled_sites.len(),
+    );
+    PageResponse::page("Settings", body)
+}
+
+/// fos://metrics - Prometheus text export of the metrics registry
+fn metrics_page() -> PageResponse {
+    // Sampled on request rather than on every change
+    let thumbnails = crate::thumbnails::with_thumbnails(|t| t.memory_used());
+    crate::metrics::set("fos_thumbnail_cache_bytes", "Memory held by tab preview thumbnails", thumbnails as f64);
+    let history = crate::history::with_history(|h| h.entries().len());
+    crate::metrics::set("fos_history_entries", "Entries in the browsing history", history as f64);
+
+    PageResponse::Text(crate::metrics::with_metrics(|m| m.to_prometheus()))
+}
+
+/// fos://about - diagnostics for "why is the browser using so much memory"
+fn about_page() -> PageResponse {
+    let mut body = String::from(
+        "<h1>About fOS-WB</h1><p><a href=\"fos://about\">Refresh</a> · \
+         <a href=\"fos://metrics\">Raw metrics</a></p>"
+    );
+
+    body.push_str(&format!(
+        "<h2>Build</h2><table>\
+         <tr><td>fOS-WB</td><td>{}</td></tr>\
+         <tr><td>WebKitGTK</td><td>{}.{}.{}</td></tr>\
+         <tr><td>GTK</td><td>{}.{}.{}</td></tr></table>",
+        env!("CARGO_PKG_VERSION"),
+        webkit6::functions::major_version(), webkit6::functions::minor_version(), webkit6::functions::micro_version(),
+        gtk4::major_version(), gtk4::minor_version(), gtk4::micro_version(),
+    ));
+
+    // WebKit runs each group of tabs in its own web process
+    let browser = crate::metrics::process_rss(std::process::id()).unwrap_or(0);
+    let children = crate::metrics::child_processes();
+    let total = browser + children.iter().map(|(_, _, rss)| rss).sum::<u64>();
+    body.push_str(&format!(
+        "<h2>Memory</h2><p><span class=\"big\">{}</span> resident in total</p><table>\
+         <tr><td>Browser (UI)</td><td>{}</td><td>{}</td></tr>",
+        format_bytes(total), std::process::id(), format_bytes(browser)
+    ));
+    for (pid, name, rss) in &children {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(name), pid, format_bytes(*rss)
+        ));
+    }
+    body.push_str("</table>");
+
+    let (tabs, unloaded, blocked) = crate::metrics::with_metrics(|m| (
+        m.gauge("fos_tabs_open").unwrap_or(0.0) as u64,
+        m.gauge("fos_tabs_unloaded").unwrap_or(0.0) as u64,
+        m.counter_total("fos_adblock_blocked_total"),
+    ));
+    let today = with_stats(|stats| stats.report(1, now_secs()).blocked);
+    let thumbnails = crate::thumbnails::with_thumbnails(|t| t.memory_used());
+    body.push_str(&format!(
+        "<h2>Tabs</h2><table>\
+         <tr><td>Open</td><td>{}</td></tr>\
+         <tr><td>Not loaded yet</td><td>{}</td></tr>\
+         <tr><td>Preview thumbnails</td><td>{}</td></tr></table>\
+         <h2>Adblocker</h2><table>\
+         <tr><td>Blocked since start</td><td>{}</td></tr>\
+         <tr><td>Blocked today</td><td>{}</td></tr></table>",
+        tabs, unloaded, format_bytes(thumbnails as u64), blocked, today
+    ));
+
+    PageResponse::page("About", body)
+}
+
+/// fos://shield - what was blocked recently, per site, and the allowed sites
+fn shield_page(uri: &FosUri) -> PageResponse {
+    if let Some(site) = uri.param("site") {
+        let allowed = match uri.action.as_str() {
+            "allow" => Some(true),
+            "block" => Some(false),
+            _ => None,
+        };
+        if let Some(allowed) = allowed {
+            with_blocking_prefs(|prefs| {
+                prefs.set_site_allowed(site, allowed);
+                prefs.save();
+            });
+            return PageResponse::Redirect("fos://shield".to_string());
+        }
+    }
+
+    if uri.action == "unhide" {
+        let site = uri.param("site").unwrap_or("");
+        let selector = uri.param("selector").unwrap_or("");
+        let removed = with_blocking_prefs(|prefs| {
+            let removed = prefs.unhide_element(site, selector);
+            prefs.save();
+            removed
+        });
+        if removed {
+            let (site, selector) = (site.to_string(), selector.to_string());
+            crate::undo::register("Blocked element removed", move || with_blocking_prefs(|prefs| {
+                prefs.hide_element(&format!("https://{}/", site), &selector);
+                prefs.save();
+            }));
+        }
+        return PageResponse::Redirect("fos://shield".to_string());
+    }
+
+    let now = now_secs();
+    let mut body = String::from(
+        "<h1>Shield</h1><p class=\"muted\">Requests blocked recently, by site (private tabs excluded). \
+         Totals over the week are at <a href=\"fos://stats\">fos://stats</a>.</p>"
+    );
+
+    let allowed: Vec<String> = with_blocking_prefs(|prefs| prefs.allowed_sites().to_vec());
+    if !allowed.is_empty() {
+        body.push_str("<h2>Blocking turned off</h2><table>");
+        for site in &allowed {
+            body.push_str(&format!(
+                "<tr><td>{site}</td><td><a href=\"{block}&amp;site={query}\">Turn blocking back on</a></td></tr>",
+                site = escape_html(site),
+                block = action_url("shield/block"),
+                query = encode_query(site),
+            ));
+        }
+        body.push_str("</table>");
+    }
+
+    let hidden = with_blocking_prefs(|prefs| prefs.hidden_elements().to_vec());
+    if !hidden.is_empty() {
+        body.push_str("<h2>Blocked elements</h2><p class=\"muted\">Added with \"Block Element\" \
+            in the page's context menu.</p><table>");
+        for rule in &hidden {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td><code>{}</code></td>\
+                 <td><a class=\"muted\" href=\"{}&amp;site={}&amp;selector={}\">remove</a></td></tr>",
+                escape_html(&rule.site),
+                escape_html(&rule.selector),
+                action_url("shield/unhide"),
+                encode_query(&rule.site),
+                encode_query(&rule.selector),
+            ));
+        }
+        body.push_str("</table>");
+    }
+
+    let sites = crate::shield::recent_by_site();
+    if sites.is_empty() {
+        body.push_str("<p class=\"muted\">Nothing blocked yet.</p>");
+    }
+    for (site, blocks) in &sites {
+        let name = if site.is_empty() { "(unknown site)" } else { site.as_str() };
+        body.push_str(&format!("<h2>{} <span class=\"muted\">{} blocked</span></h2>", escape_html(name), blocks.len()));
+        if !site.is_empty() && !allowed.contains(site) {
+            body.push_str(&format!(
+                "<p><a href=\"{}&amp;site={}\">Turn blocking off on this site</a></p>",
+                action_url("shield/allow"),
+                encode_query(site)
+            ));
+        }
+        body.push_str("<table>");
+        for block in blocks {
+            // Query strings are long and mostly tracking ids
+            let url = block.url.split('?').next().unwrap_or(&block.url);
+            body.push_str(&format!(
+                "<tr><td>{}</td><td class=\"muted\">{}</td><td class=\"muted\">{}</td></tr>",
+                escape_html(url),
+                block.category.label(),
+                format_age(now.saturating_sub(block.time)),
+            ));
+        }
+        body.push_str("</table>");
+    }
+
+    PageResponse::page("Shield", body)
+}
+
+/// Short age like `5s ago`, `3m ago`, `2h ago`
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_handles_escapes() {
+        assert_eq!(percent_decode("a%20b+c"), "a b c");
+        assert_eq!(percent_decode("a%20"), "a ");
+        assert_eq!(percent_decode("%C3%B1"), "ñ");
+    }
+
+    #[test]
+    fn percent_decode_keeps_invalid_escapes() {
+        assert_eq!(percent_decode("%+1"), "% 1");
+        assert_eq!(percent_decode("%-1x"), "%-1x");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%2"), "%2");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
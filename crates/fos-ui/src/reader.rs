@@ -0,0 +1,142 @@
+//! Reader Module - Distraction-free article view
+//!
+//! The main article is extracted in the page with readability-style DOM
+//! heuristics and kept here (in memory) for fos://reader/<tab>. The reader
+//! page is rebuilt from plain text blocks only, so no scripts, ads or
+//! remote resources of the original page survive.
+
+use serde::Deserialize;
+use std::cell::RefCell;
+
+use crate::protocol::escape_html;
+
+/// Script that extracts the article of the current page as JSON
+pub const EXTRACT_SCRIPT: &str = include_str!("pages/reader-extract.js");
+
+/// Articles kept for reader pages (oldest are dropped first)
+const MAX_ARTICLES: usize = 16;
+
+/// Smallest and largest reader font size steps
+pub const MIN_FONT_SIZE: u8 = 1;
+pub const MAX_FONT_SIZE: u8 = 5;
+
+/// An extracted article
+#[derive(Deserialize, Default, Clone)]
+pub struct Article {
+    pub title: String,
+    #[serde(default)]
+    pub byline: String,
+    #[serde(default)]
+    pub site: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub blocks: Vec<Block>,
+}
+
+/// One block of article text (heading, paragraph, list item, ...)
+#[derive(Deserialize, Clone)]
+pub struct Block {
+    pub tag: String,
+    pub text: String,
+}
+
+/// Extracted articles by tab (WebKit page id) and reader preferences
+pub struct ReaderStore {
+    articles: Vec<(u64, Article)>,
+    pub font_size: u8,
+    pub dark: bool,
+}
+
+impl Default for ReaderStore {
+    fn default() -> Self {
+        Self { articles: Vec::new(), font_size: 3, dark: false }
+    }
+}
+
+// Thread-local store (since we're running single-threaded GTK)
+thread_local! {
+    static READER: RefCell<ReaderStore> = RefCell::new(ReaderStore::default());
+}
+
+/// Run a closure with the reader store
+pub fn with_reader<R>(f: impl FnOnce(&mut ReaderStore) -> R) -> R {
+    READER.with(|store| f(&mut store.borrow_mut()))
+}
+
+/// Parse the extraction script's result; `None` if no article was found
+pub fn parse_article(json: &str) -> Option<Article> {
+    serde_json::from_str::<Article>(json)
+        .ok()
+        .filter(|a| !a.blocks.is_empty())
+}
+
+impl ReaderStore {
+    /// Keep the article extracted from a tab
+    pub fn insert(&mut self, tab: u64, article: Article) {
+        self.articles.retain(|(id, _)| *id != tab);
+        self.articles.push((tab, article));
+        if self.articles.len() > MAX_ARTICLES {
+            self.articles.remove(0);
+        }
+    }
+
+    /// Article extracted from a tab
+    pub fn article(&self, tab: u64) -> Option<&Article> {
+        self.articles.iter().find(|(id, _)| *id == tab).map(|(_, a)| a)
+    }
+}
+
+/// HTML body for an article (text only, everything escaped)
+pub(crate) fn render_article(article: &Article, tab: u64, font_size: u8, dark: bool) -> String {
+    let theme = if dark { "dark" } else { "light" };
+    let mut body = format!(
+        "<div class=\"reader-bar muted\">\
+         <a href=\"fos://reader/{tab}?size={smaller}\">A−</a> \
+         <a href=\"fos://reader/{tab}?size={larger}\">A+</a> \
+         <a href=\"fos://reader/{tab}?theme={other}\">{other_label}</a> \
+         <a href=\"{url}\">Original page</a></div>\
+         <article class=\"reader {theme} size-{size}\"><h1>{title}</h1>",
+        tab = tab,
+        smaller = font_size.saturating_sub(1).max(MIN_FONT_SIZE),
+        larger = (font_size + 1).min(MAX_FONT_SIZE),
+        other = if dark { "light" } else { "dark" },
+        other_label = if dark { "Light" } else { "Dark" },
+        url = escape_html(&article.url),
+        theme = theme,
+        size = font_size,
+        title = escape_html(&article.title),
+    );
+
+    let by = [article.byline.as_str(), article.site.as_str()]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .map(escape_html)
+        .collect::<Vec<_>>()
+        .join(" · ");
+    if !by.is_empty() {
+        body.push_str(&format!("<p class=\"muted\">{}</p>", by));
+    }
+
+    let mut in_list = false;
+    for block in &article.blocks {
+        let tag = match block.tag.as_str() {
+            "h1" | "h2" => "h2",
+            "h3" | "h4" | "h5" | "h6" => "h3",
+            "li" => "li",
+            "pre" => "pre",
+            "blockquote" => "blockquote",
+            _ => "p",
+        };
+        if (tag == "li") != in_list {
+            body.push_str(if in_list { "</ul>" } else { "<ul>" });
+            in_list = !in_list;
+        }
+        body.push_str(&format!("<{tag}>{}</{tag}>", escape_html(&block.text)));
+    }
+    if in_list {
+        body.push_str("</ul>");
+    }
+    body.push_str("</article>");
+    body
+}
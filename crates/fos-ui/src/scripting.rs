@@ -0,0 +1,148 @@
+//! Scripting Module - Isolated execution of internal scripts
+//!
+//! Internal features (reader extraction, cosmetic filters, YouTube ad
+//! skipping) run their scripts in a separate JavaScript world: they share
+//! the page's DOM but not its globals, so page scripts can neither see,
+//! override nor block them, and internal code never leaks into the page.
+//!
+//! Every run has a timeout and results are size-capped. The timeout only
+//! gives up waiting: WebKit cannot stop a script once it runs, so a runaway
+//! one keeps going in the web process. WebKit has no per-world memory
+//! limit either, so scripts are expected to be short and return small results.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::debug;
+use webkit6::gio::{self, prelude::*};
+use webkit6::glib::{self, SourceId};
+use webkit6::prelude::*;
+use webkit6::{UserContentInjectedFrames, UserContentManager, UserScript, UserScriptInjectionTime, WebView};
+
+/// JavaScript world shared by all internal scripts
+pub const WORLD_NAME: &str = "fos-internal";
+
+/// How long to wait for an internal script's result before giving up on it
+/// (the script itself is not stopped)
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest result an internal script may return
+pub const MAX_RESULT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Remembers the element last right-clicked, for the context menu's
+/// "Block Element" (the DOM event fires before WebKit shows the menu).
+/// Only the top frame: internal scripts cannot be run in a subframe.
+const CONTEXT_TARGET_SCRIPT: &str =
+    "document.addEventListener('contextmenu', e => { window.fosContextTarget = e.target; }, true);";
+
+/// Takes the remembered element for the menu being shown, so a later
+/// right-click in a subframe (which the top frame never hears of) cannot
+/// reuse a stale one
+const CLAIM_CONTEXT_TARGET_SCRIPT: &str = "(() => {
+    window.fosMenuTarget = window.fosContextTarget || null;
+    window.fosContextTarget = null;
+    return window.fosMenuTarget !== null;
+})()";
+
+/// Why an internal script produced no result
+#[derive(Debug)]
+pub enum ScriptError {
+    Timeout,
+    TooLarge(usize),
+    Failed(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Timeout => write!(f, "script timed out"),
+            ScriptError::TooLarge(len) => write!(f, "script result too large ({} bytes)", len),
+            ScriptError::Failed(message) => write!(f, "script failed: {}", message),
+        }
+    }
+}
+
+/// Run an internal script and get its result as a string
+pub fn run(
+    webview: &WebView,
+    script: &str,
+    callback: impl FnOnce(Result<String, ScriptError>) + 'static,
+) {
+    run_with_timeout(webview, script, DEFAULT_TIMEOUT, callback);
+}
+
+/// Run an internal script, reporting `ScriptError::Timeout` if no result
+/// arrives within `timeout`. Only the reply is dropped; the script keeps
+/// running in the web process until it ends.
+pub fn run_with_timeout(
+    webview: &WebView,
+    script: &str,
+    timeout: Duration,
+    callback: impl FnOnce(Result<String, ScriptError>) + 'static,
+) {
+    let cancellable = gio::Cancellable::new();
+    let timer: Rc<RefCell<Option<SourceId>>> = Rc::default();
+    {
+        let cancellable = cancellable.clone();
+        let timer_slot = timer.clone();
+        let source = glib::timeout_add_local_once(timeout, move || {
+            timer_slot.borrow_mut().take();
+            cancellable.cancel();
+        });
+        *timer.borrow_mut() = Some(source);
+    }
+
+    webview.evaluate_javascript(
+        script,
+        Some(WORLD_NAME),
+        None,
+        Some(&cancellable),
+        move |result| {
+            if let Some(source) = timer.borrow_mut().take() {
+                source.remove();
+            }
+            callback(match result {
+                Ok(value) => {
+                    let text = value.to_str().to_string();
+                    if text.len() > MAX_RESULT_BYTES {
+                        Err(ScriptError::TooLarge(text.len()))
+                    } else {
+                        Ok(text)
+                    }
+                }
+                Err(e) if e.matches(gio::IOErrorEnum::Cancelled) => Err(ScriptError::Timeout),
+                Err(e) => Err(ScriptError::Failed(e.to_string())),
+            });
+        },
+    );
+}
+
+/// Run an internal script for its side effects only
+pub fn inject(webview: &WebView, script: &str) {
+    run(webview, script, |result| {
+        if let Err(e) = result {
+            debug!("Internal script: {}", e);
+        }
+    });
+}
+
+/// When a context menu opens: whether its right-click hit an element of
+/// the top document (false for subframes, where "Block Element" cannot work)
+pub fn claim_context_target(webview: &WebView, callback: impl FnOnce(bool) + 'static) {
+    run(webview, CLAIM_CONTEXT_TARGET_SCRIPT, move |result| {
+        callback(result.is_ok_and(|found| found == "true"));
+    });
+}
+
+/// Install the scripts every page runs in the internal world
+pub(crate) fn attach(manager: &UserContentManager) {
+    manager.add_script(&UserScript::for_world(
+        CONTEXT_TARGET_SCRIPT,
+        UserContentInjectedFrames::TopFrame,
+        UserScriptInjectionTime::Start,
+        WORLD_NAME,
+        &[],
+        &[],
+    ));
+}
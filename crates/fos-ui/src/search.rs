@@ -0,0 +1,179 @@
+//! Search Module - Search engines and keyword shortcuts
+//!
+//! Features:
+//! - Several engines with OpenSearch-style URL templates (`{searchTerms}`)
+//! - Default engine chosen in the settings (`search_engine` in config.toml)
+//! - Keyword prefixes in the address bar: `w rust` searches Wikipedia,
+//!   `!w rust` works too
+//! - Custom engines persisted as JSON in the data directory
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::fs;
+use std::sync::LazyLock;
+
+use crate::config::{encode_query, with_config};
+
+/// Placeholder replaced by the query in URL templates
+pub const SEARCH_TERMS: &str = "{searchTerms}";
+
+/// A search engine
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SearchEngine {
+    pub id: String,
+    pub name: String,
+    /// Address bar prefix, e.g. `w` for `w rust`
+    pub keyword: String,
+    /// URL template containing `{searchTerms}`
+    pub url: String,
+}
+
+impl SearchEngine {
+    fn new(id: &str, name: &str, keyword: &str, url: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            keyword: keyword.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    /// Search URL for a query
+    pub fn url_for(&self, query: &str) -> String {
+        self.url.replace(SEARCH_TERMS, &encode_query(query))
+    }
+}
+
+/// All configured search engines
+#[derive(Serialize, Deserialize)]
+pub struct SearchEngineManager {
+    engines: Vec<SearchEngine>,
+}
+
+impl Default for SearchEngineManager {
+    fn default() -> Self {
+        Self {
+            engines: vec![
+                SearchEngine::new("duckduckgo", "DuckDuckGo", "d", "https://duckduckgo.com/?q={searchTerms}"),
+                SearchEngine::new("startpage", "Startpage", "s", "https://www.startpage.com/do/search?q={searchTerms}"),
+                SearchEngine::new("brave", "Brave Search", "b", "https://search.brave.com/search?q={searchTerms}"),
+                SearchEngine::new("google", "Google", "g", "https://www.google.com/search?q={searchTerms}"),
+                SearchEngine::new("wikipedia", "Wikipedia", "w", "https://en.wikipedia.org/w/index.php?search={searchTerms}"),
+            ],
+        }
+    }
+}
+
+// Thread-local manager (since we're running single-threaded GTK)
+thread_local! {
+    static ENGINES: RefCell<SearchEngineManager> = RefCell::new(SearchEngineManager::load());
+}
+
+/// Run a closure with the search engines
+pub fn with_search_engines<R>(f: impl FnOnce(&mut SearchEngineManager) -> R) -> R {
+    ENGINES.with(|engines| f(&mut engines.borrow_mut()))
+}
+
+impl SearchEngineManager {
+    /// Load engines from the data directory (built-in engines if none saved)
+    pub fn load() -> Self {
+        let path = crate::webview::get_data_dir().join("search_engines.json");
+        let loaded = fs::read_to_string(&path).ok()
+            .and_then(|data| serde_json::from_str::<Self>(&data).ok());
+        // A hand-edited or imported file may list no engines at all
+        loaded.filter(|m| !m.engines.is_empty()).unwrap_or_default()
+    }
+
+    /// Save engines to the data directory
+    pub fn save(&self) {
+        let path = crate::webview::get_data_dir().join("search_engines.json");
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            fs::write(path, json).ok();
+        }
+    }
+
+    /// All engines
+    pub fn engines(&self) -> &[SearchEngine] {
+        &self.engines
+    }
+
+    /// Engine by id
+    pub fn find(&self, id: &str) -> Option<&SearchEngine> {
+        self.engines.iter().find(|e| e.id == id)
+    }
+
+    /// Add a custom engine; the template must contain `{searchTerms}`.
+    /// Returns the new engine's id.
+    pub fn add(&mut self, name: &str, keyword: &str, url: &str) -> Option<String> {
+        let (name, keyword, url) = (name.trim(), keyword.trim().to_lowercase(), url.trim());
+        let valid = !name.is_empty()
+            && url.starts_with("http")
+            && url.contains(SEARCH_TERMS)
+            && !keyword.contains(char::is_whitespace)
+            && (keyword.is_empty() || !self.engines.iter().any(|e| e.keyword == keyword));
+        if !valid {
+            return None;
+        }
+
+        let base: String = name.to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let mut id = base.clone();
+        let mut n = 1;
+        while self.find(&id).is_some() {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+
+        self.engines.push(SearchEngine::new(&id, name, &keyword, url));
+        Some(id)
+    }
+
+    /// Remove an engine, returning it and its position (never the last one)
+    pub fn remove(&mut self, id: &str) -> Option<(usize, SearchEngine)> {
+        if self.engines.len() <= 1 {
+            return None;
+        }
+        let pos = self.engines.iter().position(|e| e.id == id)?;
+        Some((pos, self.engines.remove(pos)))
+    }
+
+    /// Put back a removed engine (used by undo)
+    pub fn restore(&mut self, pos: usize, engine: SearchEngine) {
+        if self.find(&engine.id).is_none() {
+            self.engines.insert(pos.min(self.engines.len()), engine);
+        }
+    }
+
+    /// Search URL when the input starts with an engine keyword (`w rust`, `!w rust`)
+    pub fn resolve_keyword(&self, input: &str) -> Option<String> {
+        let (keyword, query) = input.trim().split_once(char::is_whitespace)?;
+        let keyword = keyword.strip_prefix('!').unwrap_or(keyword).to_lowercase();
+        let query = query.trim();
+        if query.is_empty() {
+            return None;
+        }
+        self.engines.iter()
+            .find(|e| !e.keyword.is_empty() && e.keyword == keyword)
+            .map(|e| e.url_for(query))
+    }
+
+    /// The default engine from the settings (first engine if unknown,
+    /// the first built-in one if there are none)
+    pub fn default_engine(&self) -> &SearchEngine {
+        static BUILT_IN: LazyLock<SearchEngine> = LazyLock::new(|| SearchEngineManager::default().engines.remove(0));
+        let id = with_config(|c| c.search_engine.clone());
+        self.find(&id)
+            .or(self.engines.first())
+            .unwrap_or(&BUILT_IN)
+    }
+}
+
+/// URL for address bar text that is a search: keyword prefix or default engine
+pub fn search_url(input: &str) -> String {
+    with_search_engines(|m| {
+        m.resolve_keyword(input)
+            .unwrap_or_else(|| m.default_engine().url_for(input))
+    })
+}
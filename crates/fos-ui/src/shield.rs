@@ -0,0 +1,116 @@
+//! Shield Module - What the protections did on each page
+//!
+//! Counts blocked requests per tracker category and the cosmetic filter
+//! rules applied, per web page (keyed by WebKit page id). The counts are
+//! kept in memory only and reset on every navigation; they feed the shield
+//! panel next to the address bar.
+//!
+//! The most recent blocks (site, URL, category) are also kept in a ring
+//! buffer for the fos://shield page. Private tabs are not recorded there.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use crate::adblocker::TrackerCategory;
+use crate::history::now_secs;
+use crate::omnibox::host_of;
+
+/// Blocks remembered for fos://shield
+pub const MAX_RECENT_BLOCKS: usize = 200;
+
+/// One blocked request
+#[derive(Clone)]
+pub struct RecentBlock {
+    /// First-party site the request came from
+    pub site: String,
+    pub url: String,
+    pub category: TrackerCategory,
+    /// Unix timestamp (seconds)
+    pub time: u64,
+}
+
+/// Protection summary for one page
+#[derive(Clone, Default)]
+pub struct PageShield {
+    /// (category, blocked requests), in first-blocked order
+    pub blocked: Vec<(TrackerCategory, u64)>,
+    /// Cosmetic filter selectors applied to the page
+    pub cosmetic_rules: usize,
+}
+
+impl PageShield {
+    /// Blocked requests over all categories
+    pub fn total_blocked(&self) -> u64 {
+        self.blocked.iter().map(|(_, n)| n).sum()
+    }
+
+    /// Blocked requests in one category
+    pub fn blocked_in(&self, category: TrackerCategory) -> u64 {
+        self.blocked.iter()
+            .find(|(c, _)| *c == category)
+            .map_or(0, |(_, n)| *n)
+    }
+}
+
+// Thread-local counters (since we're running single-threaded GTK)
+thread_local! {
+    static PAGES: RefCell<HashMap<u64, PageShield>> = RefCell::new(HashMap::new());
+    static RECENT: RefCell<VecDeque<RecentBlock>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Count a blocked request on a page
+pub fn record_block(page_id: u64, category: TrackerCategory) {
+    PAGES.with(|pages| {
+        let mut pages = pages.borrow_mut();
+        let page = pages.entry(page_id).or_default();
+        match page.blocked.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, n)) => *n += 1,
+            None => page.blocked.push((category, 1)),
+        }
+    });
+}
+
+/// Remember how many cosmetic rules were applied to a page
+pub fn record_cosmetic(page_id: u64, rules: usize) {
+    PAGES.with(|pages| pages.borrow_mut().entry(page_id).or_default().cosmetic_rules = rules);
+}
+
+/// Summary for a page (empty if nothing happened yet)
+pub fn page(page_id: u64) -> PageShield {
+    PAGES.with(|pages| pages.borrow().get(&page_id).cloned().unwrap_or_default())
+}
+
+/// Start counting afresh (new navigation or closed tab)
+pub fn reset(page_id: u64) {
+    PAGES.with(|pages| pages.borrow_mut().remove(&page_id));
+}
+
+/// Remember a blocked request for fos://shield, dropping the oldest
+pub fn record_recent(url: &str, source_url: &str, category: TrackerCategory) {
+    RECENT.with(|recent| {
+        let mut recent = recent.borrow_mut();
+        if recent.len() == MAX_RECENT_BLOCKS {
+            recent.pop_front();
+        }
+        recent.push_back(RecentBlock {
+            site: host_of(source_url).to_lowercase(),
+            url: url.to_string(),
+            category,
+            time: now_secs(),
+        });
+    });
+}
+
+/// Recent blocks grouped by site, most recently active site first
+pub fn recent_by_site() -> Vec<(String, Vec<RecentBlock>)> {
+    RECENT.with(|recent| {
+        let mut sites: Vec<(String, Vec<RecentBlock>)> = Vec::new();
+        for block in recent.borrow().iter().rev() {
+            match sites.iter_mut().find(|(site, _)| *site == block.site) {
+                Some((_, blocks)) => blocks.push(block.clone()),
+                None => sites.push((block.site.clone(), vec![block.clone()])),
+            }
+        }
+        sites
+    })
+}
@@ -0,0 +1,104 @@
+//! Thumbnails Module - Tab preview cache
+//!
+//! Keeps a downscaled snapshot of recently rendered pages (by URL) for the
+//! hover preview in the tab strip. The cache lives in memory only, is bounded
+//! by a byte budget, and evicts the least recently used thumbnails first.
+
+use gtk4::gdk::{self, Texture};
+use gtk4::gdk_pixbuf::InterpType;
+use gtk4::prelude::*;
+use std::cell::RefCell;
+
+/// Width of a cached thumbnail in pixels
+pub const THUMBNAIL_WIDTH: i32 = 240;
+
+/// Memory budget for all cached thumbnails (RGBA bytes)
+const MEMORY_BUDGET: usize = 8 * 1024 * 1024;
+
+struct CachedThumbnail {
+    url: String,
+    texture: Texture,
+    bytes: usize,
+}
+
+/// Thumbnails ordered from least to most recently used
+#[derive(Default)]
+pub struct ThumbnailCache {
+    entries: Vec<CachedThumbnail>,
+    used: usize,
+}
+
+// Thread-local cache (since we're running single-threaded GTK)
+thread_local! {
+    static THUMBNAILS: RefCell<ThumbnailCache> = RefCell::new(ThumbnailCache::default());
+}
+
+/// Run a closure with the thumbnail cache
+pub fn with_thumbnails<R>(f: impl FnOnce(&mut ThumbnailCache) -> R) -> R {
+    THUMBNAILS.with(|cache| f(&mut cache.borrow_mut()))
+}
+
+impl ThumbnailCache {
+    /// Thumbnail for a URL, marking it as recently used
+    pub fn get(&mut self, url: &str) -> Option<Texture> {
+        let pos = self.entries.iter().position(|e| e.url == url)?;
+        let entry = self.entries.remove(pos);
+        let texture = entry.texture.clone();
+        self.entries.push(entry);
+        Some(texture)
+    }
+
+    /// Downscale a page snapshot and cache it, returning the thumbnail
+    pub fn insert(&mut self, url: &str, snapshot: &Texture) -> Option<Texture> {
+        let texture = downscale(snapshot)?;
+        let bytes = texture.width() as usize * texture.height() as usize * 4;
+
+        self.remove(url);
+        self.entries.push(CachedThumbnail {
+            url: url.to_string(),
+            texture: texture.clone(),
+            bytes,
+        });
+        self.used += bytes;
+
+        // Evict least recently used thumbnails until we fit the budget
+        while self.used > MEMORY_BUDGET && self.entries.len() > 1 {
+            let evicted = self.entries.remove(0);
+            self.used -= evicted.bytes;
+        }
+        Some(texture)
+    }
+
+    /// Forget the thumbnail for a URL
+    pub fn remove(&mut self, url: &str) {
+        if let Some(pos) = self.entries.iter().position(|e| e.url == url) {
+            let removed = self.entries.remove(pos);
+            self.used -= removed.bytes;
+        }
+    }
+
+    /// Drop every thumbnail (e.g. when memory runs low)
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used = 0;
+    }
+
+    /// Bytes currently held by cached thumbnails
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+}
+
+/// Scale a full-size snapshot down to thumbnail width
+fn downscale(snapshot: &Texture) -> Option<Texture> {
+    let (width, height) = (snapshot.width(), snapshot.height());
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+    let thumb_height = (height * THUMBNAIL_WIDTH / width).max(1);
+
+    #[allow(deprecated)]
+    let pixbuf = gdk::pixbuf_get_from_texture(snapshot)?;
+    let scaled = pixbuf.scale_simple(THUMBNAIL_WIDTH, thumb_height, InterpType::Bilinear)?;
+    Some(Texture::for_pixbuf(&scaled))
+}
@@ -0,0 +1,79 @@
+//! Undo Module - Undo for destructive actions
+//!
+//! Destructive actions (closing a tab, deleting bookmarks, clearing
+//! history) register a compensating action here. The most recent one is
+//! offered in a toast and can be undone with Ctrl+Z until it expires.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// How long an action can still be undone
+pub const UNDO_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Most actions kept on the stack
+const MAX_ENTRIES: usize = 20;
+
+/// Callback told about newly registered actions
+type Listener = Rc<dyn Fn(&str)>;
+
+struct UndoEntry {
+    label: String,
+    created: Instant,
+    undo: Box<dyn FnOnce()>,
+}
+
+/// Time-limited stack of compensating actions
+#[derive(Default)]
+pub struct UndoStack {
+    entries: Vec<UndoEntry>,
+    /// Called with the label of every newly registered action (shows the toast)
+    listener: Option<Listener>,
+}
+
+// Thread-local stack (since we're running single-threaded GTK)
+thread_local! {
+    static UNDO: RefCell<UndoStack> = RefCell::new(UndoStack::default());
+}
+
+/// Register the compensating action for something that was just done
+pub fn register(label: &str, undo: impl FnOnce() + 'static) {
+    let listener = UNDO.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        stack.entries.retain(|e| e.created.elapsed() < UNDO_TIMEOUT);
+        stack.entries.push(UndoEntry {
+            label: label.to_string(),
+            created: Instant::now(),
+            undo: Box::new(undo),
+        });
+        if stack.entries.len() > MAX_ENTRIES {
+            stack.entries.remove(0);
+        }
+        stack.listener.clone()
+    });
+
+    // Outside the borrow: the listener may query the stack
+    if let Some(listener) = listener {
+        listener(label);
+    }
+}
+
+/// Undo the most recent action that has not expired, returning its label
+pub fn undo_last() -> Option<String> {
+    let entry = UNDO.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        stack.entries.retain(|e| e.created.elapsed() < UNDO_TIMEOUT);
+        stack.entries.pop()
+    })?;
+
+    // Outside the borrow: undoing may register new actions
+    info!("Undo: {}", entry.label);
+    (entry.undo)();
+    Some(entry.label)
+}
+
+/// Set the callback told about newly registered actions
+pub(crate) fn set_listener(listener: impl Fn(&str) + 'static) {
+    UNDO.with(|stack| stack.borrow_mut().listener = Some(Rc::new(listener)));
+}
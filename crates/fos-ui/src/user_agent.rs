@@ -0,0 +1,90 @@
+//! User Agent Module - Per-site user agent overrides
+//!
+//! Some sites refuse or degrade WebKitGTK's own user agent. The
+//! `[user_agent]` table of config.toml sets a default and per-site
+//! overrides (subdomains included). Each value is `desktop`, `mobile`, a
+//! literal user agent string, or empty for WebKit's own. The string for a
+//! page is applied to the tab's settings before each navigation.
+
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+use crate::omnibox::host_of;
+
+/// Sent for `desktop`: a current desktop Chrome on Linux
+pub const DESKTOP_UA: &str =
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36";
+
+/// Sent for `mobile`: Safari on an iPhone
+pub const MOBILE_UA: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) \
+    AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1";
+
+/// User agent settings
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct UserAgentConfig {
+    /// Used where no site override applies (empty: WebKit's own)
+    pub default: String,
+    /// Host -> value
+    pub sites: BTreeMap<String, String>,
+}
+
+impl UserAgentConfig {
+    /// Setting that applies to a page URL: the most specific site
+    /// override, else the default
+    pub fn value_for(&self, page_url: &str) -> &str {
+        let host = host_of(page_url).to_lowercase();
+        let mut domain = host.as_str();
+        loop {
+            if let Some(value) = self.sites.get(domain) {
+                return value;
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => domain = parent,
+                _ => return &self.default,
+            }
+        }
+    }
+
+    /// User agent string for a page URL (None: WebKit's own)
+    pub fn resolve(&self, page_url: &str) -> Option<String> {
+        user_agent_string(self.value_for(page_url))
+    }
+
+    /// Whether a page gets the mobile user agent
+    pub fn is_mobile(&self, page_url: &str) -> bool {
+        self.value_for(page_url) == "mobile"
+    }
+
+    /// Set (or with `None`, remove) the override for a site, given a host or URL
+    pub fn set_site(&mut self, site: &str, value: Option<&str>) {
+        let site = host_of(site.trim()).to_lowercase();
+        if site.is_empty() {
+            return;
+        }
+        match value {
+            Some(value) => self.sites.insert(site, value.trim().to_string()),
+            None => self.sites.remove(&site),
+        };
+    }
+
+    /// "Request mobile/desktop site": switch a site between the mobile
+    /// user agent and what it would get otherwise
+    pub fn toggle_mobile(&mut self, page_url: &str) {
+        let mobile = self.is_mobile(page_url);
+        self.set_site(page_url, None);
+        if mobile == self.is_mobile(page_url) {
+            self.set_site(page_url, Some(if mobile { "desktop" } else { "mobile" }));
+        }
+    }
+}
+
+/// User agent string for a setting value (None: WebKit's own)
+pub fn user_agent_string(value: &str) -> Option<String> {
+    match value.trim() {
+        "" => None,
+        "desktop" => Some(DESKTOP_UA.to_string()),
+        "mobile" => Some(MOBILE_UA.to_string()),
+        custom => Some(custom.to_string()),
+    }
+}
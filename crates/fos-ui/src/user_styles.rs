@@ -0,0 +1,165 @@
+//! User Styles Module - Custom CSS per site
+//!
+//! Users attach their own CSS to a domain (and its subdomains), e.g. to
+//! force a dark theme on one site. Styles are persisted as JSON in the data
+//! directory, injected through WebKit's user content manager, and edited
+//! on the fos://styles page.
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::fs;
+use tracing::info;
+use webkit6::{UserContentInjectedFrames, UserContentManager, UserStyleLevel, UserStyleSheet};
+
+use crate::omnibox::host_of;
+
+/// CSS attached to one domain
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UserStyle {
+    pub id: u64,
+    /// Host the style applies to (subdomains included)
+    pub domain: String,
+    pub css: String,
+    pub enabled: bool,
+}
+
+/// All user styles
+#[derive(Serialize, Deserialize, Default)]
+pub struct UserStyleStore {
+    next_id: u64,
+    styles: Vec<UserStyle>,
+}
+
+/// Style sheets currently installed in the content managers
+#[derive(Default)]
+struct Installed {
+    managers: Vec<UserContentManager>,
+    sheets: Vec<UserStyleSheet>,
+}
+
+// Thread-local store (since we're running single-threaded GTK)
+thread_local! {
+    static STYLES: RefCell<UserStyleStore> = RefCell::new(UserStyleStore::load());
+    static INSTALLED: RefCell<Installed> = RefCell::new(Installed::default());
+}
+
+/// Run a closure with the user style store
+pub fn with_user_styles<R>(f: impl FnOnce(&mut UserStyleStore) -> R) -> R {
+    STYLES.with(|store| f(&mut store.borrow_mut()))
+}
+
+impl UserStyleStore {
+    /// Load styles from the data directory
+    pub fn load() -> Self {
+        let path = crate::webview::get_data_dir().join("styles.json");
+        if let Ok(data) = fs::read_to_string(&path) {
+            serde_json::from_str(&data).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Save styles to the data directory
+    pub fn save(&self) {
+        let path = crate::webview::get_data_dir().join("styles.json");
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            fs::write(path, json).ok();
+        }
+    }
+
+    /// All styles, in creation order
+    pub fn styles(&self) -> &[UserStyle] {
+        &self.styles
+    }
+
+    /// Add a style for a domain (or URL) and return its id
+    pub fn add(&mut self, domain: &str, css: &str) -> Option<u64> {
+        let domain = normalize_domain(domain)?;
+        self.next_id += 1;
+        self.styles.push(UserStyle {
+            id: self.next_id,
+            domain,
+            css: css.to_string(),
+            enabled: true,
+        });
+        Some(self.next_id)
+    }
+
+    /// Change the domain and CSS of a style
+    pub fn update(&mut self, id: u64, domain: &str, css: &str) {
+        let Some(domain) = normalize_domain(domain) else {
+            return;
+        };
+        if let Some(style) = self.styles.iter_mut().find(|s| s.id == id) {
+            style.domain = domain;
+            style.css = css.to_string();
+        }
+    }
+
+    /// Turn a style on or off
+    pub fn set_enabled(&mut self, id: u64, enabled: bool) {
+        if let Some(style) = self.styles.iter_mut().find(|s| s.id == id) {
+            style.enabled = enabled;
+        }
+    }
+
+    /// Remove a style, returning it if it existed
+    pub fn remove(&mut self, id: u64) -> Option<UserStyle> {
+        let pos = self.styles.iter().position(|s| s.id == id)?;
+        Some(self.styles.remove(pos))
+    }
+
+    /// Put back a removed style (used by undo)
+    pub fn restore(&mut self, style: UserStyle) {
+        if !self.styles.iter().any(|s| s.id == style.id) {
+            self.styles.push(style);
+            self.styles.sort_by_key(|s| s.id);
+        }
+    }
+}
+
+/// Bare lowercase host from user input (`https://www.Example.com/x` -> `example.com`)
+fn normalize_domain(input: &str) -> Option<String> {
+    let host = host_of(input.trim()).to_lowercase();
+    let valid = !host.is_empty()
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    valid.then_some(host)
+}
+
+/// Add a content manager for user styles and install the enabled ones
+pub(crate) fn attach(manager: &UserContentManager) {
+    INSTALLED.with(|installed| installed.borrow_mut().managers.push(manager.clone()));
+    refresh();
+}
+
+/// Reinstall style sheets after the styles changed
+pub fn refresh() {
+    let styles: Vec<UserStyle> = with_user_styles(|store| {
+        store.styles().iter().filter(|s| s.enabled).cloned().collect()
+    });
+
+    INSTALLED.with(|installed| {
+        let mut installed = installed.borrow_mut();
+        let managers = installed.managers.clone();
+        for sheet in installed.sheets.drain(..) {
+            for manager in &managers {
+                manager.remove_style_sheet(&sheet);
+            }
+        }
+        for style in &styles {
+            let allow = [format!("*://{}/*", style.domain), format!("*://*.{}/*", style.domain)];
+            let sheet = UserStyleSheet::new(
+                &style.css,
+                UserContentInjectedFrames::AllFrames,
+                UserStyleLevel::User,
+                &[allow[0].as_str(), allow[1].as_str()],
+                &[],
+            );
+            for manager in &managers {
+                manager.add_style_sheet(&sheet);
+            }
+            installed.sheets.push(sheet);
+        }
+        info!("Installed {} user styles", installed.sheets.len());
+    });
+}
@@ -1,33 +1,75 @@
 //! fOS-WB - Minimal Browser with Session Persistence
 //!
 //! Features:
-//! - Vertical tabs on left
+//! - Vertical tabs on left, in named collapsible groups
 //! - URL bar at bottom
 //! - Lazy loading: tabs only load when activated
 //! - Session persistence: saves tabs on close, restores on open
 //! - Cookie persistence: stay logged in across restarts
+//! - Bookmarks with a star button and fos://bookmarks page
+//! - Omnibox: ranked suggestions from history, bookmarks and open tabs
+//! - DNS prefetch for hovered links and the omnibox's predicted destination
+//! - Touchpad gestures: swipe for back/forward, pinch to zoom (remembered per site)
+//! - Mouse back/forward buttons
+//! - Per-site user agent, with "Request Mobile/Desktop Site" in the context menu
+//! - Context menu: open links in (background) tabs, search selection, block element
+//! - Private tabs: ephemeral network session, no extensions, nothing written to disk
+//! - Tab previews: page thumbnail when hovering a tab row
+//! - Audio indicator and per-tab mute (kept across restarts)
+//! - Reader mode (F9): article text only at fos://reader/<tab>
+//! - Find in page (Ctrl+F) with match count and highlight-all
+//! - Internal scripts run in an isolated JavaScript world
+//! - User styles: custom CSS per site (fos://styles)
+//! - Content-script extensions with storage.local (fos://extensions)
+//! - Shield panel: what was blocked on the page, with the adblock switches
+//! - Fingerprinting protection (canvas noise, common screen/CPU/WebGL values), per-site off switch
+//! - Search keywords in the address bar (`w rust` searches Wikipedia)
+//! - Settings in config.toml, edited at fos://settings and applied live
+//! - Crashed tabs reload by themselves (with backoff), then show a crash page
+//! - Undo (toast or Ctrl+Z) for closed tabs, deleted bookmarks and cleared history
 //! - Full keyboard control
 
 use gtk4::prelude::*;
 use gtk4::{
-    Application, ApplicationWindow, Box as GtkBox, Entry, Label,
+    Application, ApplicationWindow, Box as GtkBox, Button, Entry, Label,
     ListBox, ListBoxRow, Orientation, ScrolledWindow, Separator,
-    EventControllerKey, gdk::ModifierType, SelectionMode,
+    EventControllerKey, EventControllerFocus, EventControllerMotion, gdk::ModifierType,
+    SelectionMode, Picture, Popover, MenuButton, CheckButton, EditableLabel, DragSource, DropTarget, SearchEntry, Revealer, PositionType, PropagationPhase, GestureZoom, GestureClick, EventSequenceState,
 };
 use webkit6::prelude::*;
-use webkit6::{WebView, NetworkSession, CookiePersistentStorage};
-use std::cell::RefCell;
+use webkit6::{WebView, WebContext, NetworkSession, CookiePersistentStorage, UserContentManager};
+use webkit6::{ContextMenu, ContextMenuAction, ContextMenuItem, HitTestResult};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use std::path::PathBuf;
 use std::fs;
-use tracing::info;
+use tracing::{info, warn};
 use serde::{Serialize, Deserialize};
 
+use crate::omnibox::{Suggestion, SuggestionKind};
+use crate::keymap::Action;
+
 /// Tab data for session persistence
 #[derive(Serialize, Deserialize, Clone)]
 struct TabData {
     url: String,
     title: String,
+    #[serde(default)]
+    muted: bool,
+    /// Id of the tab's group
+    #[serde(default)]
+    group: Option<u64>,
+}
+
+/// A named group of adjacent tabs in the sidebar
+#[derive(Serialize, Deserialize, Clone)]
+struct TabGroup {
+    id: u64,
+    name: String,
+    /// Collapsed groups hide their rows and unload their tabs
+    #[serde(default)]
+    collapsed: bool,
 }
 
 /// Session data saved to disk
@@ -35,10 +77,12 @@ struct TabData {
 struct SessionData {
     tabs: Vec<TabData>,
     active_tab: usize,
+    #[serde(default)]
+    groups: Vec<TabGroup>,
 }
 
 /// Get data directory for browser
-fn get_data_dir() -> PathBuf {
+pub(crate) fn get_data_dir() -> PathBuf {
     let dir = dirs::data_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("fos-wb");
@@ -57,10 +101,11 @@ fn load_session() -> SessionData {
 }
 
 /// Save session to disk
-fn save_session(tabs: &[TabData], active_tab: usize) {
+fn save_session(tabs: &[TabData], active_tab: usize, groups: &[TabGroup]) {
     let data = SessionData { 
         tabs: tabs.to_vec(), 
-        active_tab 
+        active_tab,
+        groups: groups.to_vec(),
     };
     let path = get_data_dir().join("session.json");
     if let Ok(json) = serde_json::to_string_pretty(&data) {
@@ -73,14 +118,125 @@ struct BrowserState {
     tabs: Vec<TabInfo>,
     active_tab: usize,
     session: NetworkSession,
+    /// Ephemeral session shared by private tabs (created on first use)
+    private_session: Option<NetworkSession>,
+    /// User styles, scripts and extensions shared by normal tabs
+    user_content: UserContentManager,
+    /// Private tabs' content, without extensions (created on first use)
+    private_content: Option<UserContentManager>,
+    /// Tab groups, in no particular order (tabs refer to them by id)
+    groups: Vec<TabGroup>,
+    star_button: Button,
+    shield_button: MenuButton,
+    /// Turns blocking off/on for the active tab's site
+    site_toggle: Button,
+    find_bar: FindBar,
+    /// Tab that last played sound (media keys control it)
+    media_tab: Option<WebView>,
 }
 
+/// Find-in-page bar shown in the bottom bar (Ctrl+F)
+#[derive(Clone)]
+struct FindBar {
+    bar: GtkBox,
+    entry: SearchEntry,
+    count: Label,
+}
+
+/// Most matches counted and highlighted by a search
+const MAX_FIND_MATCHES: u32 = 1000;
+
+/// How long the undo toast stays visible
+const TOAST_SECONDS: u32 = 8;
+
+/// How often changed history and block statistics are saved while browsing
+const SAVE_INTERVAL_SECONDS: u32 = 60;
+
+/// Automatic reloads of a crashed tab before it is marked as crashed
+const MAX_CRASH_RESTARTS: usize = 3;
+
+/// Delay before the first automatic reload; doubles with each crash
+const CRASH_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Crashes older than this no longer count towards the limit
+const CRASH_WINDOW: Duration = Duration::from_secs(300);
+
 struct TabInfo {
     webview: WebView,
     row: ListBoxRow,
     row_label: Label,
     url: String,
     loaded: bool,
+    /// Private tabs are never saved, recorded, or persisted in any store
+    private: bool,
+    /// Id of the tab's group
+    group: Option<u64>,
+}
+
+impl TabInfo {
+    /// URL the tab shows, or will show once it is loaded
+    fn current_url(&self) -> String {
+        self.webview.uri()
+            .filter(|_| self.loaded)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| self.url.clone())
+    }
+
+    /// Load a tab that is not loaded: reload the page it was unloaded from
+    /// (keeping its history), or load its URL if it never had a page
+    fn load(&mut self) {
+        let current = self.webview.back_forward_list()
+            .and_then(|list| list.current_item())
+            .and_then(|item| item.uri());
+        if current.is_some_and(|uri| uri == self.url) {
+            self.webview.reload();
+        } else {
+            self.webview.load_uri(&self.url);
+        }
+        self.loaded = true;
+    }
+}
+
+/// Widgets that tab operations need
+#[derive(Clone)]
+struct Chrome {
+    tab_list: ListBox,
+    container: GtkBox,
+    address_bar: Entry,
+}
+
+/// Application id, also the running browser's name on the session bus
+const APP_ID: &str = "org.fos.browser";
+
+/// Whether a browser instance is running (it owns the application id)
+fn is_running() -> bool {
+    use webkit6::gio;
+    let Ok(bus) = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) else {
+        return false;
+    };
+    bus.call_sync(
+        Some("org.freedesktop.DBus"),
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+        "NameHasOwner",
+        Some(&(APP_ID,).to_variant()),
+        webkit6::glib::VariantTy::new("(b)").ok(),
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+    )
+    .ok()
+    .and_then(|reply| reply.get::<(bool,)>())
+    .is_some_and(|(owned,)| owned)
+}
+
+/// Fail if a browser instance is running: it keeps its stores in memory and
+/// would save them over files written behind its back
+pub(crate) fn ensure_not_running() -> anyhow::Result<()> {
+    if is_running() {
+        anyhow::bail!("fOS-WB is running; close it first, or it will save its own data over the import");
+    }
+    Ok(())
 }
 
 /// Run the browser
@@ -88,12 +244,15 @@ pub fn run_webview() -> anyhow::Result<()> {
     info!("Starting fOS-WB Browser");
 
     let app = Application::builder()
-        .application_id("org.fos.browser")
+        .application_id(APP_ID)
         .build();
 
     app.connect_activate(|app| {
         // Initialize adblocker on main GTK thread
         crate::adblocker::init();
+        if let Some(context) = WebContext::default() {
+            crate::protocol::register(&context);
+        }
         build_ui(app);
     });
 
@@ -123,10 +282,35 @@ fn build_ui(app: &Application) {
         info!("Cookies will persist to {:?}", cookies_path);
     }
     
+    let star_button = Button::with_label("☆");
+    star_button.set_tooltip_text(Some("Bookmark this page (Ctrl+D)"));
+    star_button.add_css_class("flat");
+
+    let find_bar = build_find_bar();
+    let shield_button = build_shield_button();
+    let site_toggle = Button::new();
+    site_toggle.add_css_class("flat");
+    refresh_site_toggle(&site_toggle, "");
+
+    let user_content = UserContentManager::new();
+    crate::user_styles::attach(&user_content);
+    crate::scripting::attach(&user_content);
+    crate::fingerprint::attach(&user_content);
+    crate::extensions::attach(&user_content);
+
     let state = Rc::new(RefCell::new(BrowserState {
         tabs: Vec::new(),
         active_tab: 0,
         session: session.clone(),
+        private_session: None,
+        private_content: None,
+        user_content,
+        groups: Vec::new(),
+        star_button: star_button.clone(),
+        shield_button: shield_button.clone(),
+        site_toggle: site_toggle.clone(),
+        find_bar: find_bar.clone(),
+        media_tab: None,
     }));
 
     let window = ApplicationWindow::builder()
@@ -166,6 +350,19 @@ fn build_ui(app: &Application) {
     webview_container.set_hexpand(true);
     content_box.append(&webview_container);
 
+    // === UNDO TOAST ===
+    let toast = Revealer::new();
+    let toast_box = GtkBox::new(Orientation::Horizontal, 8);
+    toast_box.add_css_class("toast");
+    toast_box.set_halign(gtk4::Align::Center);
+    let toast_label = Label::new(None);
+    let undo_button = Button::with_label("Undo");
+    undo_button.set_tooltip_text(Some("Undo (Ctrl+Z)"));
+    toast_box.append(&toast_label);
+    toast_box.append(&undo_button);
+    toast.set_child(Some(&toast_box));
+    content_box.append(&toast);
+
     // === BOTTOM BAR ===
     let bottom_bar = GtkBox::new(Orientation::Horizontal, 0);
     bottom_bar.set_margin_start(8);
@@ -177,19 +374,34 @@ fn build_ui(app: &Application) {
     address_bar.set_hexpand(true);
     address_bar.set_placeholder_text(Some("Enter URL or search..."));
 
+    bottom_bar.append(&find_bar.bar);
     bottom_bar.append(&address_bar);
+    bottom_bar.append(&site_toggle);
+    bottom_bar.append(&shield_button);
+    bottom_bar.append(&star_button);
     content_box.append(&bottom_bar);
     main_box.append(&content_box);
 
+    let chrome = Chrome {
+        tab_list: tab_list.clone(),
+        container: webview_container.clone(),
+        address_bar: address_bar.clone(),
+    };
+
     // Load saved session or create default tab
     let saved_session = load_session();
     if saved_session.tabs.is_empty() {
-        create_tab(&state, &tab_list, &webview_container, &address_bar, "https://duckduckgo.com", "DuckDuckGo", true);
+        let homepage = crate::config::with_config(|c| c.homepage.clone());
+        create_tab(&state, &chrome, &homepage, "New Tab", true, false);
     } else {
         // Restore saved tabs with their titles
         for (i, tab_data) in saved_session.tabs.iter().enumerate() {
             let load_now = i == saved_session.active_tab;
-            create_tab(&state, &tab_list, &webview_container, &address_bar, &tab_data.url, &tab_data.title, load_now);
+            create_tab(&state, &chrome, &tab_data.url, &tab_data.title, load_now, false);
+            if let Some(tab) = state.borrow_mut().tabs.last_mut() {
+                tab.webview.set_is_muted(tab_data.muted);
+                tab.group = tab_data.group;
+            }
         }
         // Set correct active tab
         let mut s = state.borrow_mut();
@@ -199,21 +411,41 @@ fn build_ui(app: &Application) {
                 tab.webview.set_visible(i == saved_session.active_tab);
             }
         }
+        s.groups = saved_session.groups;
+        // The active tab's group is always open
+        if let Some(active_group) = s.tabs.get(s.active_tab).and_then(|t| t.group) {
+            if let Some(group) = s.groups.iter_mut().find(|g| g.id == active_group) {
+                group.collapsed = false;
+            }
+        }
+        apply_group_visibility(&s);
         info!("Restored {} tabs from session", saved_session.tabs.len());
     }
+    attach_group_headers(&state, &tab_list);
+
+    // === Save history and block statistics periodically ===
+    // (so a crash or kill loses at most one interval)
+    gtk4::glib::timeout_add_seconds_local(SAVE_INTERVAL_SECONDS, || {
+        crate::history::with_history(|h| h.save_if_dirty());
+        crate::blockstats::with_stats(|b| b.save_if_dirty());
+        gtk4::glib::ControlFlow::Continue
+    });
 
     // === Save session on close ===
     {
         let s = state.clone();
         window.connect_close_request(move |_| {
             let state = s.borrow();
-            let tabs: Vec<TabData> = state.tabs.iter().map(|t| {
+            // Private tabs are dropped; keep the active index pointing at the same tab
+            let active_tab = state.tabs[..state.active_tab.min(state.tabs.len())]
+                .iter()
+                .filter(|t| !t.private)
+                .count();
+            let tabs: Vec<TabData> = state.tabs.iter().filter(|t| !t.private).map(|t| {
                 // Get title from the row label (always up-to-date)
                 let label_title = t.row_label.text().to_string();
                 TabData {
-                    url: t.webview.uri()
-                        .map(|u| u.to_string())
-                        .unwrap_or_else(|| t.url.clone()),
+                    url: t.current_url(),
                     title: if label_title.is_empty() || label_title == "Loading..." {
                         t.webview.title()
                             .map(|s| s.to_string())
@@ -221,9 +453,18 @@ fn build_ui(app: &Application) {
                     } else {
                         label_title
                     },
+                    muted: t.webview.is_muted(),
+                    group: t.group,
                 }
             }).collect();
-            save_session(&tabs, state.active_tab);
+            // Only groups that still have saved tabs
+            let groups: Vec<TabGroup> = state.groups.iter()
+                .filter(|g| tabs.iter().any(|t| t.group == Some(g.id)))
+                .cloned()
+                .collect();
+            save_session(&tabs, active_tab, &groups);
+            crate::history::with_history(|h| h.save());
+            crate::blockstats::with_stats(|b| b.save());
             info!("Session saved with {} tabs", tabs.len());
             gtk4::glib::Propagation::Proceed
         });
@@ -233,12 +474,20 @@ fn build_ui(app: &Application) {
     {
         let s = state.clone();
         let addr = address_bar.clone();
-        tab_list.connect_row_selected(move |_, row| {
+        tab_list.connect_row_selected(move |tl, row| {
             if let Some(row) = row {
                 let idx = row.index() as usize;
                 if let Ok(mut state) = s.try_borrow_mut() {
                     if idx < state.tabs.len() {
                         state.active_tab = idx;
+
+                        // Selecting a tab (e.g. with Ctrl+O/L) opens its group
+                        let group = state.tabs[idx].group;
+                        if let Some(group) = state.groups.iter_mut().find(|g| Some(g.id) == group && g.collapsed) {
+                            group.collapsed = false;
+                            apply_group_visibility(&state);
+                            invalidate_group_headers(tl);
+                        }
                         
                         for (i, tab) in state.tabs.iter().enumerate() {
                             tab.webview.set_visible(i == idx);
@@ -246,9 +495,8 @@ fn build_ui(app: &Application) {
                         
                         // Lazy load
                         if !state.tabs[idx].loaded {
-                            let url = state.tabs[idx].url.clone();
-                            state.tabs[idx].webview.load_uri(&url);
-                            state.tabs[idx].loaded = true;
+                            state.tabs[idx].load();
+                            update_tab_metrics(&state);
                         }
                         
                         if let Some(uri) = state.tabs[idx].webview.uri() {
@@ -256,121 +504,101 @@ fn build_ui(app: &Application) {
                         } else {
                             addr.set_text(&state.tabs[idx].url);
                         }
+                        refresh_star(&state.star_button, &addr.text());
+                        refresh_site_toggle(&state.site_toggle, &addr.text());
+                        refresh_shield(&state.shield_button, &state.tabs[idx].webview);
                     }
                 }
             }
         });
     }
 
-    // === KEYBOARD SHORTCUTS ===
+    // === KEYBOARD SHORTCUTS (see the keymap module; rebindable in config.toml) ===
     let key_controller = EventControllerKey::new();
     {
         let s = state.clone();
+        let chrome = chrome.clone();
         let tl = tab_list.clone();
-        let container = webview_container.clone();
         let addr = address_bar.clone();
         key_controller.connect_key_pressed(move |_, key, _, modifiers| {
-            if modifiers.contains(ModifierType::CONTROL_MASK) {
-                match key.name().as_deref() {
-                    // Ctrl+T: New tab
-                    Some("t") => {
-                        create_tab(&s, &tl, &container, &addr, "https://duckduckgo.com", "New Tab", false);
-                        return gtk4::glib::Propagation::Stop;
-                    }
-                    // Ctrl+W: Close tab
-                    Some("w") => {
-                        let mut state = s.borrow_mut();
-                        if state.tabs.len() > 1 {
-                            let idx = state.active_tab;
-                            if idx < state.tabs.len() {
-                                container.remove(&state.tabs[idx].webview);
-                                tl.remove(&state.tabs[idx].row);
-                                state.tabs.remove(idx);
-                                
-                                let new_idx = idx.saturating_sub(1).min(state.tabs.len().saturating_sub(1));
-                                state.active_tab = new_idx;
-                                if new_idx < state.tabs.len() {
-                                    state.tabs[new_idx].webview.set_visible(true);
-                                    tl.select_row(Some(&state.tabs[new_idx].row));
-                                }
-                            }
-                        }
-                        return gtk4::glib::Propagation::Stop;
+            let Some(action) = crate::config::with_config(|c| c.keys.action_for(key, modifiers)) else {
+                return gtk4::glib::Propagation::Proceed;
+            };
+            match action {
+                Action::Reader => toggle_reader(&s),
+                Action::NewTab => {
+                    let homepage = crate::config::with_config(|c| c.homepage.clone());
+                    create_tab(&s, &chrome, &homepage, "New Tab", false, false);
+                }
+                Action::NewPrivateTab => {
+                    let homepage = crate::config::with_config(|c| c.homepage.clone());
+                    create_tab(&s, &chrome, &homepage, "Private Tab", true, true);
+                }
+                Action::CloseTab => {
+                    let idx = s.borrow().active_tab;
+                    close_tab(&s, &chrome, idx);
+                }
+                // Pages keep their own undo
+                Action::Undo if page_has_focus(&s) => return gtk4::glib::Propagation::Proceed,
+                Action::Undo => perform_undo(&s),
+                Action::ClearHistory => clear_history(),
+                Action::GroupTab => toggle_tab_group(&s, &tl),
+                Action::NextGroup => cycle_group(&s, &tl),
+                Action::Reload => {
+                    let state = s.borrow();
+                    if state.active_tab < state.tabs.len() {
+                        state.tabs[state.active_tab].webview.reload();
                     }
-                    // Ctrl+R: Reload
-                    Some("r") => {
-                        let state = s.borrow();
-                        if state.active_tab < state.tabs.len() {
-                            state.tabs[state.active_tab].webview.reload();
+                }
+                Action::FocusUrl => {
+                    addr.grab_focus();
+                    addr.select_region(0, -1);
+                }
+                Action::PreviousTab | Action::NextTab => {
+                    let mut state = s.borrow_mut();
+                    let new_idx = match action {
+                        Action::PreviousTab => state.active_tab.checked_sub(1),
+                        _ => Some(state.active_tab + 1).filter(|i| *i < state.tabs.len()),
+                    };
+                    if let Some(new_idx) = new_idx {
+                        state.tabs[state.active_tab].webview.set_visible(false);
+                        state.active_tab = new_idx;
+                        state.tabs[new_idx].webview.set_visible(true);
+                        if !state.tabs[new_idx].loaded {
+                            state.tabs[new_idx].load();
+                            update_tab_metrics(&state);
                         }
-                        return gtk4::glib::Propagation::Stop;
-                    }
-                    // Ctrl+I: Focus URL bar
-                    Some("i") => {
-                        addr.grab_focus();
-                        addr.select_region(0, -1);
-                        return gtk4::glib::Propagation::Stop;
-                    }
-                    // Ctrl+O: Tab above
-                    Some("o") => {
-                        let mut state = s.borrow_mut();
-                        if state.tabs.len() > 1 && state.active_tab > 0 {
-                            state.tabs[state.active_tab].webview.set_visible(false);
-                            let new_idx = state.active_tab - 1;
-                            state.active_tab = new_idx;
-                            state.tabs[new_idx].webview.set_visible(true);
-                            if !state.tabs[new_idx].loaded {
-                                let url = state.tabs[new_idx].url.clone();
-                                state.tabs[new_idx].webview.load_uri(&url);
-                                state.tabs[new_idx].loaded = true;
-                            }
-                            tl.select_row(Some(&state.tabs[new_idx].row));
-                            if let Some(uri) = state.tabs[new_idx].webview.uri() {
-                                addr.set_text(&uri);
-                            }
+                        tl.select_row(Some(&state.tabs[new_idx].row));
+                        refresh_shield(&state.shield_button, &state.tabs[new_idx].webview);
+                        if let Some(uri) = state.tabs[new_idx].webview.uri() {
+                            addr.set_text(&uri);
+                            refresh_star(&state.star_button, &uri);
+                            refresh_site_toggle(&state.site_toggle, &uri);
                         }
-                        return gtk4::glib::Propagation::Stop;
                     }
-                    // Ctrl+L: Tab below
-                    Some("l") => {
-                        let mut state = s.borrow_mut();
-                        if state.tabs.len() > 1 && state.active_tab < state.tabs.len() - 1 {
-                            state.tabs[state.active_tab].webview.set_visible(false);
-                            let new_idx = state.active_tab + 1;
-                            state.active_tab = new_idx;
-                            state.tabs[new_idx].webview.set_visible(true);
-                            if !state.tabs[new_idx].loaded {
-                                let url = state.tabs[new_idx].url.clone();
-                                state.tabs[new_idx].webview.load_uri(&url);
-                                state.tabs[new_idx].loaded = true;
-                            }
-                            tl.select_row(Some(&state.tabs[new_idx].row));
-                            if let Some(uri) = state.tabs[new_idx].webview.uri() {
-                                addr.set_text(&uri);
-                            }
-                        }
-                        return gtk4::glib::Propagation::Stop;
+                }
+                Action::Back => {
+                    let state = s.borrow();
+                    if state.active_tab < state.tabs.len() {
+                        state.tabs[state.active_tab].webview.go_back();
                     }
-                    // Ctrl+K: Go back
-                    Some("k") => {
-                        let state = s.borrow();
-                        if state.active_tab < state.tabs.len() {
-                            state.tabs[state.active_tab].webview.go_back();
-                        }
-                        return gtk4::glib::Propagation::Stop;
+                }
+                Action::Forward => {
+                    let state = s.borrow();
+                    if state.active_tab < state.tabs.len() {
+                        state.tabs[state.active_tab].webview.go_forward();
                     }
-                    // Ctrl+Ñ: Go forward
-                    Some("ntilde") | Some("Ntilde") | Some("ñ") | Some("Ñ") => {
-                        let state = s.borrow();
-                        if state.active_tab < state.tabs.len() {
-                            state.tabs[state.active_tab].webview.go_forward();
-                        }
-                        return gtk4::glib::Propagation::Stop;
+                }
+                Action::Find => open_find_bar(&s),
+                Action::Mute => {
+                    let state = s.borrow();
+                    if let Some(tab) = state.tabs.get(state.active_tab) {
+                        tab.webview.set_is_muted(!tab.webview.is_muted());
                     }
-                    _ => {}
                 }
+                Action::Bookmark => toggle_bookmark(&s),
             }
-            gtk4::glib::Propagation::Proceed
+            gtk4::glib::Propagation::Stop
         });
     }
     window.add_controller(key_controller);
@@ -379,25 +607,127 @@ fn build_ui(app: &Application) {
     {
         let s = state.clone();
         address_bar.connect_activate(move |entry| {
-            let text = entry.text().to_string();
-            let url = if text.starts_with("http") {
-                text
-            } else if text.contains('.') {
-                format!("https://{}", text)
-            } else {
-                format!("https://duckduckgo.com/?q={}", text.replace(' ', "+"))
-            };
+            let url = resolve_input(&entry.text());
+            navigate(&s, &url);
+        });
+    }
+    attach_omnibox(&state, &address_bar, &tab_list);
+
+    // Find bar
+    {
+        let s = state.clone();
+        find_bar.entry.connect_search_changed(move |entry| {
+            find_in_page(&s, &entry.text());
+        });
+        let s = state.clone();
+        find_bar.entry.connect_activate(move |_| {
+            with_find_controller(&s, |fc| fc.search_next());
+        });
+        let s = state.clone();
+        find_bar.entry.connect_next_match(move |_| {
+            with_find_controller(&s, |fc| fc.search_next());
+        });
+        let s = state.clone();
+        find_bar.entry.connect_previous_match(move |_| {
+            with_find_controller(&s, |fc| fc.search_previous());
+        });
+        let s = state.clone();
+        find_bar.entry.connect_stop_search(move |_| {
+            close_find_bar(&s);
+        });
+
+        // Shift+Enter: previous match
+        let keys = EventControllerKey::new();
+        let s = state.clone();
+        keys.connect_key_pressed(move |_, key, _, modifiers| {
+            use gtk4::gdk::Key;
+            if (key == Key::Return || key == Key::KP_Enter) && modifiers.contains(ModifierType::SHIFT_MASK) {
+                with_find_controller(&s, |fc| fc.search_previous());
+                return gtk4::glib::Propagation::Stop;
+            }
+            gtk4::glib::Propagation::Proceed
+        });
+        find_bar.entry.add_controller(keys);
+    }
+
+    // Undo toast: shown for each destructive action, hidden after a few seconds
+    {
+        let generation = Rc::new(Cell::new(0u64));
+        let revealer = toast.clone();
+        crate::undo::set_listener(move |label| {
+            toast_label.set_text(label);
+            revealer.set_reveal_child(true);
+            generation.set(generation.get() + 1);
+            let current = generation.get();
+            let latest = generation.clone();
+            let rev = revealer.clone();
+            gtk4::glib::timeout_add_seconds_local_once(TOAST_SECONDS, move || {
+                if latest.get() == current {
+                    rev.set_reveal_child(false);
+                }
+            });
+        });
+
+        let s = state.clone();
+        let revealer = toast.clone();
+        undo_button.connect_clicked(move |_| {
+            revealer.set_reveal_child(false);
+            perform_undo(&s);
+        });
+    }
 
-            let mut state = s.borrow_mut();
-            let idx = state.active_tab;
-            if idx < state.tabs.len() {
-                state.tabs[idx].webview.load_uri(&url);
-                state.tabs[idx].url = url;
-                state.tabs[idx].loaded = true;
+    // Apply settings edited at fos://settings or by hand
+    {
+        let s = state.clone();
+        crate::config::watch(move || {
+            crate::fingerprint::refresh();
+            let policy = acceleration_policy();
+            for tab in &s.borrow().tabs {
+                if let Some(settings) = webkit6::prelude::WebViewExt::settings(&tab.webview) {
+                    settings.set_hardware_acceleration_policy(policy);
+                    settings.set_enable_dns_prefetching(crate::config::with_config(|c| c.dns_prefetch));
+                }
+                apply_user_agent(&tab.webview, &tab.current_url());
+            }
+        });
+    }
+
+    // Shield panel, rebuilt for the active tab each time it opens
+    if let Some(popover) = shield_button.popover() {
+        let changed = Rc::new(Cell::new(false));
+        let s = state.clone();
+        let c = changed.clone();
+        popover.connect_show(move |popover| populate_shield_panel(&s, popover, &c));
+
+        // Reload so the new switches apply to the page
+        let s = state.clone();
+        popover.connect_closed(move |_| {
+            if changed.replace(false) {
+                let state = s.borrow();
+                if let Some(tab) = state.tabs.get(state.active_tab) {
+                    tab.webview.reload();
+                }
             }
         });
     }
 
+    // Blocking on/off for the active site
+    {
+        let s = state.clone();
+        site_toggle.connect_clicked(move |_| toggle_site_blocking(&s));
+    }
+
+    watch_memory_pressure(&state);
+    export_media_controls(&state);
+
+    // Star button
+    {
+        let s = state.clone();
+        star_button.connect_clicked(move |_| {
+            toggle_bookmark(&s);
+        });
+    }
+
     // CSS
     let css = gtk4::CssProvider::new();
     css.load_from_data(r#"
@@ -405,6 +735,16 @@ fn build_ui(app: &Application) {
         .sidebar listbox { background: transparent; }
         .sidebar listbox row { padding: 6px 10px; border-radius: 4px; margin: 1px 4px; }
         .sidebar listbox row:selected { background: alpha(@accent_color, 0.2); }
+        .sidebar listbox row.private { background: alpha(#9141ac, 0.25); }
+        .sidebar listbox row button.audio { padding: 0 2px; min-height: 0; min-width: 0; }
+        .omnibox listbox row { padding: 4px 8px; }
+        .toast { padding: 6px 12px; margin: 4px; border-radius: 6px; background: alpha(@window_fg_color, 0.1); }
+        .sidebar popover picture { border-radius: 4px; }
+        .shield { padding: 6px; }
+        .sidebar .group { padding: 6px 4px 0 4px; font-weight: bold; }
+        .sidebar .group button { padding: 0 4px; min-height: 0; min-width: 0; }
+        .sidebar listbox row:drop(active) { box-shadow: inset 0 2px @accent_color; }
+        .sidebar listbox row.collapsed { padding: 0; margin: 0; min-height: 0; }
     "#);
     gtk4::style_context_add_provider_for_display(
         &gtk4::gdk::Display::default().unwrap(),
@@ -420,17 +760,24 @@ fn build_ui(app: &Application) {
 
 fn create_tab(
     state: &Rc<RefCell<BrowserState>>,
-    tab_list: &ListBox,
-    container: &GtkBox,
-    address_bar: &Entry,
+    chrome: &Chrome,
     url: &str,
     title: &str,
     load_now: bool,
+    private: bool,
 ) {
-    // Use shared persistent session for all tabs
-    let session = state.borrow().session.clone();
+    let Chrome { tab_list, container, address_bar } = chrome;
+
+    // Use shared persistent session for all tabs (ephemeral one for private tabs)
+    let (session, user_content) = if private {
+        (private_session(state), private_content(state))
+    } else {
+        let state = state.borrow();
+        (state.session.clone(), state.user_content.clone())
+    };
     let webview = WebView::builder()
         .network_session(&session)
+        .user_content_manager(&user_content)
         .build();
 
     // Settings - optimized for speed and video playback
@@ -439,8 +786,11 @@ fn create_tab(
         settings.set_enable_smooth_scrolling(true);
         settings.set_enable_developer_extras(false);
         
-        // Performance optimizations (HW accel disabled due to flickering on this GPU)
-        settings.set_hardware_acceleration_policy(webkit6::HardwareAccelerationPolicy::Never);
+        // Touchpad: two-finger horizontal swipe for back/forward (WebKit animates the page)
+        settings.set_enable_back_forward_navigation_gestures(true);
+        
+        // Performance optimizations (HW accel off by default due to flickering on some GPUs)
+        settings.set_hardware_acceleration_policy(acceleration_policy());
         settings.set_enable_site_specific_quirks(true);    // Browser compatibility
         
         // Video playback - critical for seeking to work
@@ -456,20 +806,32 @@ fn create_tab(
         // Caching - faster page loads
         settings.set_enable_page_cache(true);
         settings.set_enable_offline_web_application_cache(true);
-        settings.set_enable_dns_prefetching(true);
+        settings.set_enable_dns_prefetching(crate::config::with_config(|c| c.dns_prefetch));
         
         // Iframe permissions for embedded players
         settings.set_allow_file_access_from_file_urls(true);
         settings.set_allow_universal_access_from_file_urls(true);
         settings.set_javascript_can_open_windows_automatically(true);
     }
+    apply_user_agent(&webview, url);
     
     // Adblocker - intercept resource loads (skip for media)
-    webview.connect_decide_policy(|wv, decision, decision_type| {
+    let s = state.clone();
+    webview.connect_decide_policy(move |wv, decision, decision_type| {
         use webkit6::PolicyDecisionType;
         
         if decision_type == PolicyDecisionType::NavigationAction 
             || decision_type == PolicyDecisionType::NewWindowAction {
+            // Send the user agent configured for the destination site
+            if decision_type == PolicyDecisionType::NavigationAction {
+                let uri = decision.downcast_ref::<webkit6::NavigationPolicyDecision>()
+                    .and_then(|d| d.navigation_action())
+                    .and_then(|mut action| action.request())
+                    .and_then(|request| request.uri());
+                if let Some(uri) = uri {
+                    apply_user_agent(wv, &uri);
+                }
+            }
             // Allow navigation
             return false;
         }
@@ -503,7 +865,23 @@ fn create_tab(
                         }
                         
                         let source = wv.uri().map(|s| s.to_string()).unwrap_or_default();
-                        if crate::adblocker::should_block(&uri, &source, "other") {
+                        if let Some(category) = crate::adblocker::check(&uri, &source, "other") {
+                            let size = response_decision.response()
+                                .map(|r| r.content_length())
+                                .unwrap_or(0);
+                            if !is_private(wv) {
+                                crate::blockstats::record_block(&uri, &source, category, size);
+                            }
+                            crate::shield::record_block(wv.page_id(), category);
+                            if !is_private(wv) {
+                                crate::shield::record_recent(&uri, &source, category);
+                            }
+                            crate::metrics::inc(
+                                "fos_adblock_blocked_total",
+                                "Requests blocked by the adblocker",
+                                &[("category", category.name())],
+                            );
+                            refresh_shield_if_active(&s, wv);
                             decision.ignore();
                             return true;
                         }
@@ -515,6 +893,14 @@ fn create_tab(
         false // Let WebKit handle it
     });
 
+    // Watchdog - reload the tab when its web process dies
+    {
+        let crashes: Rc<RefCell<Vec<Instant>>> = Rc::default();
+        webview.connect_web_process_terminated(move |wv, reason| {
+            recover_tab(wv, reason, &crashes);
+        });
+    }
+
     // Fullscreen handlers - prevent window state corruption
     {
         let win = container.root().and_downcast::<ApplicationWindow>();
@@ -532,6 +918,108 @@ fn create_tab(
         }
     }
 
+    // Find in page: show how many matches the search found
+    if let Some(find_controller) = webview.find_controller() {
+        let count = state.borrow().find_bar.count.clone();
+        find_controller.connect_counted_matches(move |_, matches| {
+            count.set_text(&match matches {
+                0 => "No matches".to_string(),
+                1 => "1 match".to_string(),
+                n if n >= MAX_FIND_MATCHES => format!("{}+ matches", MAX_FIND_MATCHES),
+                n => format!("{} matches", n),
+            });
+        });
+        let count = state.borrow().find_bar.count.clone();
+        find_controller.connect_failed_to_find_text(move |_| {
+            count.set_text("No matches");
+        });
+    }
+
+    // Touchpad pinch: zoom the page and remember the level for the site
+    {
+        let pinch = GestureZoom::new();
+        pinch.set_propagation_phase(PropagationPhase::Capture);
+        let start_zoom = Rc::new(Cell::new(1.0));
+        {
+            let wv = webview.clone();
+            let start = start_zoom.clone();
+            pinch.connect_begin(move |gesture, _| {
+                gesture.set_state(EventSequenceState::Claimed);
+                start.set(wv.zoom_level());
+            });
+        }
+        {
+            let wv = webview.clone();
+            pinch.connect_scale_changed(move |_, scale| {
+                let level = (start_zoom.get() * scale).clamp(crate::zoom::MIN_ZOOM, crate::zoom::MAX_ZOOM);
+                wv.set_zoom_level(level);
+            });
+        }
+        {
+            let wv = webview.clone();
+            pinch.connect_end(move |_, _| {
+                if is_private(&wv) {
+                    return;
+                }
+                if let Some(uri) = wv.uri() {
+                    crate::zoom::with_zoom(|z| {
+                        z.set_zoom(&uri, wv.zoom_level());
+                        z.save();
+                    });
+                }
+            });
+        }
+        webview.add_controller(pinch);
+    }
+
+    // Mouse back/forward buttons (8 and 9)
+    {
+        let buttons = GestureClick::new();
+        buttons.set_button(0);
+        buttons.set_propagation_phase(PropagationPhase::Capture);
+        let wv = webview.clone();
+        buttons.connect_pressed(move |gesture, _, _, _| {
+            match gesture.current_button() {
+                8 => wv.go_back(),
+                9 => wv.go_forward(),
+                _ => return,
+            }
+            gesture.set_state(EventSequenceState::Claimed);
+        });
+        webview.add_controller(buttons);
+    }
+
+    // Resolve a link's host once it has been hovered for a moment
+    {
+        let pending: Rc<RefCell<Option<gtk4::glib::SourceId>>> = Rc::default();
+        webview.connect_mouse_target_changed(move |wv, hit, _| {
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+            }
+            let Some(link) = hit.link_uri().filter(|_| hit.context_is_link()) else {
+                return;
+            };
+            let (wv, link, slot) = (wv.clone(), link.to_string(), pending.clone());
+            let source = gtk4::glib::timeout_add_local_once(crate::prefetch::HOVER_DELAY, move || {
+                slot.borrow_mut().take();
+                if let Some(session) = wv.network_session() {
+                    crate::prefetch::prefetch(&session, &link);
+                }
+            });
+            *pending.borrow_mut() = Some(source);
+        });
+    }
+
+    // Context menu: tabs instead of windows, search selection, block element
+    {
+        let s = state.clone();
+        let chrome = chrome.clone();
+        webview.connect_context_menu(move |wv, menu, hit| {
+            customize_context_menu(&s, &chrome, wv, menu, hit);
+            false
+        });
+    }
+
     if load_now {
         webview.load_uri(url);
     }
@@ -546,7 +1034,69 @@ fn create_tab(
     row_label.set_halign(gtk4::Align::Start);
     row_label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
     row_label.set_max_width_chars(16);
-    row.set_child(Some(&row_label));
+    row_label.set_hexpand(true);
+
+    let row_box = GtkBox::new(Orientation::Horizontal, 4);
+    if private {
+        // Private marker next to the title
+        row_box.append(&Label::new(Some("🕶")));
+        row.add_css_class("private");
+        row.set_tooltip_text(Some("Private tab"));
+    }
+    row_box.append(&row_label);
+
+    // Speaker shown while the tab plays sound (or is muted); click to toggle
+    let audio_button = Button::new();
+    audio_button.add_css_class("flat");
+    audio_button.add_css_class("audio");
+    {
+        let wv = webview.clone();
+        audio_button.connect_clicked(move |_| wv.set_is_muted(!wv.is_muted()));
+    }
+    {
+        let btn = audio_button.clone();
+        let s = state.clone();
+        webview.connect_is_playing_audio_notify(move |wv| {
+            refresh_audio_indicator(&btn, wv);
+            media_playback_changed(&s, wv);
+        });
+    }
+    {
+        let btn = audio_button.clone();
+        webview.connect_is_muted_notify(move |wv| refresh_audio_indicator(&btn, wv));
+    }
+    refresh_audio_indicator(&audio_button, &webview);
+    row_box.append(&audio_button);
+    row.set_child(Some(&row_box));
+    attach_tab_preview(state, &row);
+
+    // Rows can be dragged onto another row to reorder tabs, or onto a
+    // group header to join that group
+    let drag = DragSource::new();
+    drag.set_actions(gtk4::gdk::DragAction::MOVE);
+    {
+        let wv = webview.clone();
+        drag.connect_prepare(move |_, _, _| {
+            Some(gtk4::gdk::ContentProvider::for_value(&wv.page_id().to_value()))
+        });
+    }
+    row.add_controller(drag);
+
+    let drop = DropTarget::new(u64::static_type(), gtk4::gdk::DragAction::MOVE);
+    {
+        let s = state.clone();
+        let tl = tab_list.clone();
+        let target = row.clone();
+        drop.connect_drop(move |_, value, _, _| {
+            let Ok(page_id) = value.get::<u64>() else {
+                return false;
+            };
+            let (s, tl, target) = (s.clone(), tl.clone(), target.clone());
+            gtk4::glib::idle_add_local_once(move || drop_tab_on_row(&s, &tl, page_id, &target));
+            true
+        });
+    }
+    row.add_controller(drop);
 
     // Update tab title
     {
@@ -554,6 +1104,9 @@ fn create_tab(
         webview.connect_title_notify(move |wv| {
             if let Some(title) = wv.title() {
                 lbl.set_text(&title);
+                if let Some(uri) = wv.uri().filter(|_| !is_private(wv)) {
+                    crate::history::with_history(|h| h.update_title(&uri, &title));
+                }
             }
         });
     }
@@ -569,6 +1122,8 @@ fn create_tab(
                     if std::ptr::eq(&state.tabs[state.active_tab].webview, &wv) {
                         if let Some(uri) = webview.uri() {
                             addr.set_text(&uri);
+                            refresh_star(&state.star_button, &uri);
+                            refresh_site_toggle(&state.site_toggle, &uri);
                         }
                     }
                 }
@@ -578,25 +1133,62 @@ fn create_tab(
 
     // Inject adblock scripts when page loads
     {
+        let s = state.clone();
+        let load_started: Cell<Option<Instant>> = Cell::new(None);
         webview.connect_load_changed(move |wv, event| {
             use webkit6::LoadEvent;
             
+            // Shield counts are per page
+            if event == LoadEvent::Started {
+                crate::shield::reset(wv.page_id());
+                refresh_shield_if_active(&s, wv);
+                load_started.set(Some(Instant::now()));
+            }
+            if event == LoadEvent::Finished {
+                if let Some(started) = load_started.take() {
+                    crate::metrics::observe(
+                        "fos_page_load_seconds",
+                        "Time from navigation start to load finished",
+                        started.elapsed().as_secs_f64(),
+                    );
+                }
+            }
+
+            // Restore the zoom level remembered for this site
+            if event == LoadEvent::Committed {
+                apply_site_zoom(wv);
+            }
+
+            // Record finished page loads in history (internal pages excluded)
+            if event == LoadEvent::Finished {
+                record_history(wv);
+                capture_thumbnail(wv, |_| {});
+            }
+
             // Inject scripts when DOM is ready
             if event == LoadEvent::Committed || event == LoadEvent::Finished {
                 if let Some(uri) = wv.uri() {
                     let uri_str = uri.to_string();
                     
                     // Inject cosmetic filters (element hiding CSS)
-                    let cosmetic_css = crate::adblocker::get_cosmetic_filters(&uri_str);
+                    let adblock = crate::config::with_config(|c| c.adblock.clone());
+                    let (cosmetic_css, cosmetic_rules) = if adblock.enabled && adblock.cosmetic_filters {
+                        crate::adblocker::get_cosmetic_filters(&uri_str)
+                    } else {
+                        (String::new(), 0)
+                    };
+                    crate::shield::record_cosmetic(wv.page_id(), cosmetic_rules);
                     if !cosmetic_css.is_empty() {
                         let cosmetic_script = crate::adblocker::get_cosmetic_script(&cosmetic_css);
-                        wv.evaluate_javascript(&cosmetic_script, None, None, None::<&gtk4::gio::Cancellable>, |_| {});
+                        crate::scripting::inject(wv, &cosmetic_script);
                     }
                     
                     // Inject YouTube ad-skip script
-                    if uri_str.contains("youtube.com") || uri_str.contains("youtu.be") {
+                    if adblock.enabled && adblock.youtube_adskip
+                        && !crate::adblocker::with_blocking_prefs(|p| p.is_site_allowed(&uri_str))
+                        && (uri_str.contains("youtube.com") || uri_str.contains("youtu.be")) {
                         let youtube_script = crate::adblocker::get_youtube_adskip_script();
-                        wv.evaluate_javascript(youtube_script, None, None, None::<&gtk4::gio::Cancellable>, |_| {});
+                        crate::scripting::inject(wv, youtube_script);
                     }
                 }
             }
@@ -618,8 +1210,11 @@ fn create_tab(
             row_label: row_label.clone(),
             url: url.to_string(),
             loaded: load_now,
+            private,
+            group: None,
         });
         s.active_tab = s.tabs.len() - 1;
+        update_tab_metrics(&s);
     }
 
     webview.set_visible(true);
@@ -627,6 +1222,1313 @@ fn create_tab(
     address_bar.set_text(url);
 }
 
+/// Show whether a tab is playing sound or muted on its row
+fn refresh_audio_indicator(button: &Button, webview: &WebView) {
+    let muted = webview.is_muted();
+    button.set_visible(muted || webview.is_playing_audio());
+    button.set_label(if muted { "🔇" } else { "🔊" });
+    button.set_tooltip_text(Some(if muted { "Unmute tab (Ctrl+M)" } else { "Mute tab (Ctrl+M)" }));
+}
+
+/// Close a tab (never the last one); undo reopens it in the same place
+fn close_tab(state: &Rc<RefCell<BrowserState>>, chrome: &Chrome, idx: usize) {
+    let (tab, next_row) = {
+        let mut s = state.borrow_mut();
+        if s.tabs.len() <= 1 || idx >= s.tabs.len() {
+            return;
+        }
+        let active = s.tabs[s.active_tab.min(s.tabs.len() - 1)].webview.clone();
+        let tab = s.tabs.remove(idx);
+        crate::shield::reset(tab.webview.page_id());
+        update_tab_metrics(&s);
+        chrome.container.remove(&tab.webview);
+        chrome.tab_list.remove(&tab.row);
+
+        s.active_tab = if tab.webview == active {
+            idx.saturating_sub(1).min(s.tabs.len() - 1)
+        } else {
+            s.tabs.iter().position(|t| t.webview == active).unwrap_or(0)
+        };
+        apply_group_visibility(&s);
+        let next = &s.tabs[s.active_tab];
+        next.webview.set_visible(true);
+        (tab, next.row.clone())
+    };
+    invalidate_group_headers(&chrome.tab_list);
+    // Outside the borrow so the selection handler can lazy-load the tab
+    chrome.tab_list.select_row(Some(&next_row));
+
+    let url = tab.current_url();
+    let title = tab.row_label.text().to_string();
+    let muted = tab.webview.is_muted();
+    let private = tab.private;
+    let s = state.clone();
+    let chrome = chrome.clone();
+    crate::undo::register("Tab closed", move || {
+        create_tab(&s, &chrome, &url, &title, true, private);
+        let last = {
+            let state = s.borrow();
+            if let Some(tab) = state.tabs.last() {
+                tab.webview.set_is_muted(muted);
+            }
+            state.tabs.len() - 1
+        };
+        move_tab(&s, &chrome.tab_list, last, idx.min(last));
+    });
+}
+
+/// Move a tab to another position, keeping the active tab selected
+fn move_tab(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox, from: usize, to: usize) {
+    let active_row = {
+        let mut s = state.borrow_mut();
+        if from == to || from >= s.tabs.len() || to >= s.tabs.len() {
+            return;
+        }
+        let active = s.tabs[s.active_tab.min(s.tabs.len() - 1)].webview.clone();
+        let tab = s.tabs.remove(from);
+        tab_list.remove(&tab.row);
+        tab_list.insert(&tab.row, to as i32);
+        s.tabs.insert(to, tab);
+        s.active_tab = s.tabs.iter().position(|t| t.webview == active).unwrap_or(0);
+        s.tabs[s.active_tab].row.clone()
+    };
+    tab_list.select_row(Some(&active_row));
+}
+
+/// Whether keyboard focus is inside the active tab's page
+fn page_has_focus(state: &Rc<RefCell<BrowserState>>) -> bool {
+    let state = state.borrow();
+    state.tabs.get(state.active_tab).is_some_and(|t| has_focus_within(&t.webview))
+}
+
+/// Undo the last destructive action and refresh what it may have changed
+fn perform_undo(state: &Rc<RefCell<BrowserState>>) {
+    if crate::undo::undo_last().is_none() {
+        return;
+    }
+    let state = state.borrow();
+    if let Some(tab) = state.tabs.get(state.active_tab) {
+        // Internal pages (e.g. fos://bookmarks) show the restored data
+        if let Some(uri) = tab.webview.uri() {
+            if uri.starts_with("fos://") {
+                tab.webview.reload();
+            }
+            refresh_star(&state.star_button, &uri);
+            refresh_site_toggle(&state.site_toggle, &uri);
+        }
+    }
+}
+
+/// Clear browsing history; undo puts it back
+fn clear_history() {
+    let entries = crate::history::with_history(|h| {
+        let entries = h.clear();
+        h.save();
+        entries
+    });
+    info!("Cleared {} history entries", entries.len());
+    crate::undo::register("History cleared", move || crate::history::with_history(|h| {
+        h.restore(entries);
+        h.save();
+    }));
+}
+
+/// Hardware acceleration policy from the settings
+fn acceleration_policy() -> webkit6::HardwareAccelerationPolicy {
+    if crate::config::with_config(|c| c.hardware_acceleration) {
+        webkit6::HardwareAccelerationPolicy::Always
+    } else {
+        webkit6::HardwareAccelerationPolicy::Never
+    }
+}
+
+/// Ephemeral session for private tabs: no cookies, cache or storage on disk
+fn private_session(state: &Rc<RefCell<BrowserState>>) -> NetworkSession {
+    state.borrow_mut()
+        .private_session
+        .get_or_insert_with(|| {
+            info!("Created ephemeral session for private tabs");
+            NetworkSession::new_ephemeral()
+        })
+        .clone()
+}
+
+/// Content manager for private tabs: like the shared one, but without
+/// extensions, whose storage.local would be written to disk
+fn private_content(state: &Rc<RefCell<BrowserState>>) -> UserContentManager {
+    state.borrow_mut()
+        .private_content
+        .get_or_insert_with(|| {
+            let manager = UserContentManager::new();
+            crate::user_styles::attach(&manager);
+            crate::scripting::attach(&manager);
+            crate::fingerprint::attach(&manager);
+            manager
+        })
+        .clone()
+}
+
+/// Reload a tab whose web process crashed, waiting longer after each crash;
+/// after too many crashes show a crash page that keeps the tab's URL
+fn recover_tab(webview: &WebView, reason: webkit6::WebProcessTerminationReason, crashes: &Rc<RefCell<Vec<Instant>>>) {
+    if reason == webkit6::WebProcessTerminationReason::TerminatedByApi {
+        return;
+    }
+    let Some(url) = webview.uri().map(|u| u.to_string()) else {
+        return;
+    };
+
+    let reason_label = if reason == webkit6::WebProcessTerminationReason::ExceededMemoryLimit {
+        "memory"
+    } else {
+        "crash"
+    };
+    crate::metrics::inc("fos_tab_crashes_total", "Web processes that died", &[("reason", reason_label)]);
+
+    let now = Instant::now();
+    let count = {
+        let mut crashes = crashes.borrow_mut();
+        crashes.retain(|t| now.duration_since(*t) < CRASH_WINDOW);
+        crashes.push(now);
+        crashes.len()
+    };
+
+    if count > MAX_CRASH_RESTARTS {
+        warn!("Tab crashed {} times, giving up: {}", count, url);
+        let why = if reason == webkit6::WebProcessTerminationReason::ExceededMemoryLimit {
+            "used too much memory"
+        } else {
+            "crashed"
+        };
+        let url_html = crate::protocol::escape_html(&url);
+        let page = format!(
+            "<!DOCTYPE html><html><head><title>Tab crashed</title></head>\
+             <body style=\"font-family: sans-serif; max-width: 600px; margin: 64px auto;\">\
+             <h1>This tab {why}</h1><p>It {why} {count} times in a row, so it was not reloaded again.</p>\
+             <p><a href=\"{url}\">Reload {url}</a></p></body></html>",
+            why = why,
+            count = count,
+            url = url_html,
+        );
+        // The crash page takes the tab's URL, so reloading retries the site
+        webview.load_alternate_html(&page, &url, None);
+        return;
+    }
+
+    let delay = CRASH_BACKOFF * 2u32.pow(count as u32 - 1);
+    warn!("Tab crashed ({:?}), reloading in {:?}: {}", reason, delay, url);
+    let webview = webview.clone();
+    gtk4::glib::timeout_add_local_once(delay, move || webview.load_uri(&url));
+}
+
+/// Register for media keys (MPRIS); they control the tab that last played
+/// sound, or the active tab
+fn export_media_controls(state: &Rc<RefCell<BrowserState>>) {
+    let s = state.clone();
+    crate::media::export(move |command| {
+        let Ok(state) = s.try_borrow() else {
+            return;
+        };
+        let target = state.media_tab.clone()
+            .filter(|wv| state.tabs.iter().any(|t| t.webview == *wv))
+            .or_else(|| state.tabs.get(state.active_tab).map(|t| t.webview.clone()));
+        if let Some(webview) = target {
+            crate::scripting::inject(&webview, command.script());
+        }
+    });
+}
+
+/// Report a tab starting or stopping sound to the desktop's media controls
+fn media_playback_changed(state: &Rc<RefCell<BrowserState>>, webview: &WebView) {
+    let Ok(mut state) = state.try_borrow_mut() else {
+        return;
+    };
+    let playing = webview.is_playing_audio();
+    if playing {
+        state.media_tab = Some(webview.clone());
+    } else if state.media_tab.as_ref() != Some(webview) {
+        return;
+    }
+    // Private pages' titles are not shown outside the browser
+    let title = webview.title()
+        .filter(|_| !is_private(webview))
+        .map(|t| t.to_string())
+        .unwrap_or_default();
+    crate::media::set_status(playing, &title);
+}
+
+/// React to the system running low on memory (Linux PSI via GMemoryMonitor):
+/// drop tab previews first, then unload background tabs
+fn watch_memory_pressure(state: &Rc<RefCell<BrowserState>>) {
+    use gtk4::gio::{MemoryMonitor, MemoryMonitorWarningLevel};
+
+    let monitor = MemoryMonitor::dup_default();
+    let s = state.clone();
+    monitor.connect_low_memory_warning(move |_, level| {
+        let name = match level {
+            MemoryMonitorWarningLevel::Low => "low",
+            MemoryMonitorWarningLevel::Medium => "medium",
+            _ => "critical",
+        };
+        warn!("System memory pressure: {}", name);
+        crate::metrics::inc("fos_memory_pressure_total", "Low memory warnings from the system", &[("level", name)]);
+
+        crate::thumbnails::with_thumbnails(|cache| cache.clear());
+        if level != MemoryMonitorWarningLevel::Low {
+            unload_background_tabs(&s);
+        }
+    });
+    // The monitor is a process-wide singleton that outlives this function
+}
+
+/// Unload loaded tabs that are not visible or playing sound; they reload
+/// their page when selected again, like tabs restored from the session
+fn unload_background_tabs(state: &Rc<RefCell<BrowserState>>) {
+    let Ok(mut state) = state.try_borrow_mut() else {
+        return;
+    };
+    let active = state.active_tab;
+    let mut unloaded = 0;
+    for (i, tab) in state.tabs.iter_mut().enumerate() {
+        if i != active && unload_tab(tab) {
+            unloaded += 1;
+        }
+    }
+    update_tab_metrics(&state);
+    info!("Unloaded {} background tabs", unloaded);
+}
+
+/// Unload a tab's page unless it is playing sound or private: its web
+/// process is terminated, freeing the page's memory, while the tab keeps its
+/// URL, title and history and loads again when selected. Returns whether the
+/// tab was unloaded.
+fn unload_tab(tab: &mut TabInfo) -> bool {
+    if !tab.loaded || tab.private || tab.webview.is_playing_audio() {
+        return false;
+    }
+    tab.url = tab.current_url();
+    tab.loaded = false;
+    // Reported as TerminatedByApi, which recover_tab leaves alone
+    tab.webview.terminate_web_process();
+    true
+}
+
+/// Show group headers above the first row of each group
+fn attach_group_headers(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox) {
+    let s = state.clone();
+    let tl = tab_list.clone();
+    tab_list.set_header_func(move |row, before| {
+        // Rows move while the state is borrowed; headers are refreshed afterwards
+        let Ok(state) = s.try_borrow() else {
+            return;
+        };
+        let group_of = |r: &ListBoxRow| state.tabs.iter().find(|t| &t.row == r).and_then(|t| t.group);
+        let group = group_of(row).and_then(|id| state.groups.iter().find(|g| g.id == id));
+        match group {
+            Some(group) if before.and_then(group_of) != Some(group.id) => {
+                row.set_header(Some(&build_group_header(&s, &tl, group)));
+            }
+            _ => row.set_header(None::<&gtk4::Widget>),
+        }
+    });
+    tab_list.invalidate_headers();
+}
+
+/// Header of a group: collapse arrow and editable name; tabs dropped on it join the group
+fn build_group_header(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox, group: &TabGroup) -> GtkBox {
+    let id = group.id;
+    let header = GtkBox::new(Orientation::Horizontal, 4);
+    header.add_css_class("group");
+
+    let arrow = Button::with_label(if group.collapsed { "▸" } else { "▾" });
+    arrow.add_css_class("flat");
+    arrow.set_tooltip_text(Some(if group.collapsed { "Expand group" } else { "Collapse group (unloads its tabs)" }));
+    {
+        let s = state.clone();
+        let tl = tab_list.clone();
+        let collapsed = group.collapsed;
+        arrow.connect_clicked(move |_| set_group_collapsed(&s, &tl, id, !collapsed));
+    }
+    header.append(&arrow);
+
+    let name = EditableLabel::new(&group.name);
+    name.set_hexpand(true);
+    name.set_tooltip_text(Some("Double-click to rename"));
+    {
+        let s = state.clone();
+        name.connect_editing_notify(move |label| {
+            let text = label.text().trim().to_string();
+            if label.is_editing() || text.is_empty() {
+                return;
+            }
+            if let Some(group) = s.borrow_mut().groups.iter_mut().find(|g| g.id == id) {
+                group.name = text;
+            }
+        });
+    }
+    header.append(&name);
+
+    let drop = DropTarget::new(u64::static_type(), gtk4::gdk::DragAction::MOVE);
+    {
+        let s = state.clone();
+        let tl = tab_list.clone();
+        drop.connect_drop(move |_, value, _, _| {
+            let Ok(page_id) = value.get::<u64>() else {
+                return false;
+            };
+            // The header is rebuilt by the move, so do it after the drop
+            let s = s.clone();
+            let tl = tl.clone();
+            gtk4::glib::idle_add_local_once(move || move_tab_to_group(&s, &tl, page_id, id));
+            true
+        });
+    }
+    header.add_controller(drop);
+    header
+}
+
+/// Refresh group headers once the current state borrow is over
+fn invalidate_group_headers(tab_list: &ListBox) {
+    let tl = tab_list.clone();
+    gtk4::glib::idle_add_local_once(move || tl.invalidate_headers());
+}
+
+/// Hide the rows of collapsed groups; the first one stays as a bare
+/// anchor for the group header
+fn apply_group_visibility(state: &BrowserState) {
+    let mut previous = None;
+    for tab in &state.tabs {
+        let collapsed = state.groups.iter().any(|g| Some(g.id) == tab.group && g.collapsed);
+        let first = tab.group != previous;
+        previous = tab.group;
+
+        tab.row.set_visible(!collapsed || first);
+        if let Some(child) = tab.row.child() {
+            child.set_visible(!collapsed);
+        }
+        if collapsed && first {
+            tab.row.add_css_class("collapsed");
+        } else {
+            tab.row.remove_css_class("collapsed");
+        }
+    }
+}
+
+/// Collapse a group (unloading its tabs) or expand it
+fn set_group_collapsed(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox, id: u64, collapsed: bool) {
+    if collapsed {
+        // Move away from the group first; a group holding every tab stays open
+        let other = {
+            let s = state.borrow();
+            if s.tabs.get(s.active_tab).is_some_and(|t| t.group == Some(id)) {
+                match s.tabs.iter().find(|t| t.group != Some(id)) {
+                    Some(tab) => Some(tab.row.clone()),
+                    None => return,
+                }
+            } else {
+                None
+            }
+        };
+        if let Some(row) = other {
+            tab_list.select_row(Some(&row));
+        }
+    }
+
+    let mut s = state.borrow_mut();
+    if let Some(group) = s.groups.iter_mut().find(|g| g.id == id) {
+        group.collapsed = collapsed;
+    }
+    if collapsed {
+        for tab in s.tabs.iter_mut().filter(|t| t.group == Some(id)) {
+            unload_tab(tab);
+        }
+    }
+    apply_group_visibility(&s);
+    update_tab_metrics(&s);
+    invalidate_group_headers(tab_list);
+}
+
+/// Move a tab into a group, placing it after the group's last tab
+fn move_tab_to_group(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox, page_id: u64, id: u64) {
+    let (from, to) = {
+        let mut s = state.borrow_mut();
+        let Some(from) = s.tabs.iter().position(|t| t.webview.page_id() == page_id) else {
+            return;
+        };
+        s.tabs[from].group = Some(id);
+        // A tab dropped on a collapsed group opens it if it is the active one
+        if from == s.active_tab {
+            if let Some(group) = s.groups.iter_mut().find(|g| g.id == id) {
+                group.collapsed = false;
+            }
+        }
+        let last = s.tabs.iter().enumerate()
+            .filter(|(i, t)| *i != from && t.group == Some(id))
+            .map(|(i, _)| i)
+            .next_back();
+        let to = match last {
+            Some(last) if last > from => last,
+            Some(last) => last + 1,
+            None => from,
+        };
+        (from, to)
+    };
+    move_tab(state, tab_list, from, to);
+    apply_group_visibility(&state.borrow());
+    invalidate_group_headers(tab_list);
+}
+
+/// Move a dragged tab to the position of the row it was dropped on,
+/// joining that row's group
+fn drop_tab_on_row(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox, page_id: u64, target: &ListBoxRow) {
+    let (from, to) = {
+        let mut s = state.borrow_mut();
+        let from = s.tabs.iter().position(|t| t.webview.page_id() == page_id);
+        let to = s.tabs.iter().position(|t| &t.row == target);
+        let (Some(from), Some(to)) = (from, to) else {
+            return;
+        };
+        s.tabs[from].group = s.tabs[to].group;
+        (from, to)
+    };
+    move_tab(state, tab_list, from, to);
+    apply_group_visibility(&state.borrow());
+    invalidate_group_headers(tab_list);
+}
+
+/// Ctrl+G: put the active tab in a new group, or take it out of its group
+/// (it then moves below the group so the group stays together)
+fn toggle_tab_group(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox) {
+    let (from, to) = {
+        let mut s = state.borrow_mut();
+        let active = s.active_tab;
+        let Some(group) = s.tabs.get(active).map(|t| t.group) else {
+            return;
+        };
+        match group {
+            None => {
+                let id = s.groups.iter().map(|g| g.id).max().unwrap_or(0) + 1;
+                s.groups.push(TabGroup { id, name: format!("Group {}", id), collapsed: false });
+                s.tabs[active].group = Some(id);
+                (active, active)
+            }
+            Some(id) => {
+                s.tabs[active].group = None;
+                let last = s.tabs.iter().rposition(|t| t.group == Some(id));
+                s.groups.retain(|g| g.id != id || last.is_some());
+                (active, last.filter(|l| *l > active).unwrap_or(active))
+            }
+        }
+    };
+    move_tab(state, tab_list, from, to);
+    apply_group_visibility(&state.borrow());
+    invalidate_group_headers(tab_list);
+}
+
+/// Ctrl+Shift+G: select the first tab of the next group (wrapping around)
+fn cycle_group(state: &Rc<RefCell<BrowserState>>, tab_list: &ListBox) {
+    let row = {
+        let s = state.borrow();
+        let current = s.tabs.get(s.active_tab).and_then(|t| t.group);
+        // First tab of every group, in sidebar order
+        let mut starts: Vec<(usize, u64)> = Vec::new();
+        for (i, tab) in s.tabs.iter().enumerate() {
+            if let Some(id) = tab.group.filter(|id| !starts.iter().any(|(_, g)| g == id)) {
+                starts.push((i, id));
+            }
+        }
+        starts.iter()
+            .find(|(i, id)| *i > s.active_tab && Some(*id) != current)
+            .or(starts.iter().find(|(_, id)| Some(*id) != current))
+            .map(|(i, _)| s.tabs[*i].row.clone())
+    };
+    if let Some(row) = row {
+        // Selecting opens the group if it is collapsed
+        tab_list.select_row(Some(&row));
+    }
+}
+
+/// Report open and not-yet-loaded tab counts to the metrics registry
+fn update_tab_metrics(state: &BrowserState) {
+    let unloaded = state.tabs.iter().filter(|t| !t.loaded).count();
+    crate::metrics::set("fos_tabs_open", "Open tabs", state.tabs.len() as f64);
+    crate::metrics::set("fos_tabs_unloaded", "Tabs restored but not loaded yet", unloaded as f64);
+}
+
+/// Add the browser's entries to WebKit's context menu: links and images
+/// open in tabs (not windows), selections are searched with the default
+/// engine, and the clicked element can be blocked on the site
+fn customize_context_menu(
+    state: &Rc<RefCell<BrowserState>>,
+    chrome: &Chrome,
+    webview: &WebView,
+    menu: &ContextMenu,
+    hit: &HitTestResult,
+) {
+    let private = is_private(webview);
+    // Web pages may not open internal pages (they could not link to them either)
+    let internal_page = webview.uri().is_some_and(|u| u.starts_with("fos://"));
+    let allowed = |target: &str| internal_page || !target.starts_with("fos:");
+    for item in menu.items() {
+        if matches!(
+            item.stock_action(),
+            ContextMenuAction::OpenLinkInNewWindow | ContextMenuAction::OpenImageInNewWindow
+        ) {
+            menu.remove(&item);
+        }
+    }
+
+    // After WebKit's "Open Link" / first entry
+    let mut position = menu.items().iter()
+        .position(|i| i.stock_action() == ContextMenuAction::OpenLink)
+        .map_or(0, |p| p as i32 + 1);
+    let mut insert = |item: ContextMenuItem| {
+        menu.insert(&item, position);
+        position += 1;
+    };
+
+    if let Some(link) = hit.link_uri().filter(|l| hit.context_is_link() && allowed(l)) {
+        let link = link.to_string();
+        let title = hit.link_label().map(|l| l.to_string()).unwrap_or_else(|| link.clone());
+        for (label, background) in [("Open Link in New Tab", false), ("Open Link in Background Tab", true)] {
+            let (s, chrome, link, title) = (state.clone(), chrome.clone(), link.clone(), title.clone());
+            insert(context_menu_item(label, move || open_in_new_tab(&s, &chrome, &link, &title, private, background)));
+        }
+    }
+    if let Some(image) = hit.image_uri().filter(|i| hit.context_is_image() && allowed(i)) {
+        let (s, chrome, image) = (state.clone(), chrome.clone(), image.to_string());
+        insert(context_menu_item("Open Image in New Tab", move || {
+            open_in_new_tab(&s, &chrome, &image, &image, private, false);
+        }));
+    }
+
+    if hit.context_is_selection() {
+        let engine = crate::search::with_search_engines(|m| m.default_engine().name.clone());
+        let (s, chrome, wv) = (state.clone(), chrome.clone(), webview.clone());
+        menu.append(&ContextMenuItem::new_separator());
+        menu.append(&context_menu_item(&format!("Search {} for Selection", engine), move || {
+            let (s, chrome) = (s.clone(), chrome.clone());
+            crate::scripting::run(&wv, "window.getSelection().toString()", move |result| {
+                let Some(text) = result.ok().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()) else {
+                    return;
+                };
+                let url = crate::search::search_url(&text);
+                open_in_new_tab(&s, &chrome, &url, &text, private, false);
+            });
+        }));
+    }
+
+    // "Block Element" is enabled once the element is known: right-clicks in
+    // frames (where the internal world cannot run) leave it greyed out. The
+    // element is claimed for every menu, so none is left over for later.
+    let block = gtk4::gio::SimpleAction::new("fos-context-item", None);
+    block.set_enabled(false);
+    {
+        let wv = webview.clone();
+        block.connect_activate(move |_, _| block_context_element(&wv));
+        let block = block.clone();
+        crate::scripting::claim_context_target(webview, move |found| block.set_enabled(found));
+    }
+
+    let page_uri = webview.uri().filter(|u| u.starts_with("http://") || u.starts_with("https://"));
+    if let Some(uri) = page_uri.filter(|_| !hit.context_is_editable()) {
+        menu.append(&ContextMenuItem::new_separator());
+        menu.append(&ContextMenuItem::from_gaction(&block, "Block Element", None));
+
+        // Private tabs do not write the site to the settings
+        if !private {
+            let mobile = crate::config::with_config(|c| c.user_agent.is_mobile(&uri));
+            let label = if mobile { "Request Desktop Site" } else { "Request Mobile Site" };
+            let wv = webview.clone();
+            menu.append(&context_menu_item(label, move || toggle_mobile_site(&wv)));
+        }
+    }
+}
+
+/// A context menu entry running `activate`
+fn context_menu_item(label: &str, activate: impl Fn() + 'static) -> ContextMenuItem {
+    let action = gtk4::gio::SimpleAction::new("fos-context-item", None);
+    action.connect_activate(move |_, _| activate());
+    ContextMenuItem::from_gaction(&action, label, None)
+}
+
+/// Open a URL in a new tab next to the others; a background tab leaves the
+/// current tab selected
+fn open_in_new_tab(
+    state: &Rc<RefCell<BrowserState>>,
+    chrome: &Chrome,
+    url: &str,
+    title: &str,
+    private: bool,
+    background: bool,
+) {
+    let current = {
+        let state = state.borrow();
+        state.tabs.get(state.active_tab).map(|t| t.row.clone())
+    };
+    create_tab(state, chrome, url, title, true, private);
+    if let Some(row) = current.filter(|_| background) {
+        chrome.tab_list.select_row(Some(&row));
+    }
+}
+
+/// Hide the element last right-clicked and remember a cosmetic rule for it
+/// on the site (private tabs only hide it on the current page)
+fn block_context_element(webview: &WebView) {
+    let wv = webview.clone();
+    crate::scripting::run(webview, crate::adblocker::BLOCK_ELEMENT_SCRIPT, move |result| {
+        let selector = match result {
+            Ok(selector) if !selector.is_empty() => selector,
+            Ok(_) => return,
+            Err(e) => {
+                warn!("Block element: {}", e);
+                return;
+            }
+        };
+        let Some(uri) = wv.uri().filter(|_| !is_private(&wv)) else {
+            return;
+        };
+        crate::adblocker::with_blocking_prefs(|p| {
+            p.hide_element(&uri, &selector);
+            p.save();
+        });
+        info!("Blocked element {} on {}", selector, crate::omnibox::host_of(&uri));
+        let rules = crate::shield::page(wv.page_id()).cosmetic_rules;
+        crate::shield::record_cosmetic(wv.page_id(), rules + 1);
+    });
+}
+
+/// Use the user agent configured for a page's site (see the user_agent module)
+fn apply_user_agent(webview: &WebView, url: &str) {
+    if let Some(settings) = webkit6::prelude::WebViewExt::settings(webview) {
+        let user_agent = crate::config::with_config(|c| c.user_agent.resolve(url));
+        settings.set_user_agent(user_agent.as_deref());
+    }
+}
+
+/// "Request mobile/desktop site" for the page's site, then reload it
+fn toggle_mobile_site(webview: &WebView) {
+    let Some(uri) = webview.uri().filter(|u| u.starts_with("http")) else {
+        return;
+    };
+    crate::config::with_config(|c| {
+        c.user_agent.toggle_mobile(&uri);
+        c.save();
+    });
+    apply_user_agent(webview, &uri);
+    webview.reload();
+}
+
+/// Whether a webview belongs to a private tab
+fn is_private(webview: &WebView) -> bool {
+    webview.network_session().is_some_and(|s| s.is_ephemeral())
+}
+
+/// Apply the zoom level remembered for the page's site
+fn apply_site_zoom(webview: &WebView) {
+    if let Some(uri) = webview.uri() {
+        webview.set_zoom_level(crate::zoom::with_zoom(|z| z.zoom_for(&uri)));
+    }
+}
+
+/// Record the page a webview finished loading (web pages only)
+fn record_history(webview: &WebView) {
+    if is_private(webview) {
+        return;
+    }
+    let Some(uri) = webview.uri().filter(|u| u.starts_with("http")) else {
+        return;
+    };
+    let title = webview.title().map(|t| t.to_string()).unwrap_or_default();
+    crate::history::with_history(|h| h.record_visit(&uri, &title));
+}
+
+/// Snapshot a visible webview into the thumbnail cache.
+/// Private tabs get a preview but are never cached.
+fn capture_thumbnail(webview: &WebView, done: impl FnOnce(Option<gtk4::gdk::Texture>) + 'static) {
+    let Some(uri) = webview.uri().filter(|_| webview.is_mapped()) else {
+        done(None);
+        return;
+    };
+    let private = is_private(webview);
+    webview.snapshot(
+        webkit6::SnapshotRegion::Visible,
+        webkit6::SnapshotOptions::NONE,
+        None::<&gtk4::gio::Cancellable>,
+        move |result| {
+            let thumbnail = result.ok().and_then(|texture| {
+                crate::thumbnails::with_thumbnails(|cache| {
+                    let thumbnail = cache.insert(&uri, &texture);
+                    if private {
+                        cache.remove(&uri);
+                    }
+                    thumbnail
+                })
+            });
+            done(thumbnail);
+        },
+    );
+}
+
+/// Hover preview for a tab row: a live snapshot for the visible tab,
+/// the last cached thumbnail for background tabs
+fn attach_tab_preview(state: &Rc<RefCell<BrowserState>>, row: &ListBoxRow) {
+    let popover = Popover::new();
+    popover.set_parent(row);
+    popover.set_autohide(false);
+    popover.set_position(PositionType::Right);
+    popover.set_can_target(false);
+
+    let preview = GtkBox::new(Orientation::Vertical, 4);
+    let picture = Picture::new();
+    picture.set_size_request(crate::thumbnails::THUMBNAIL_WIDTH, -1);
+    picture.set_can_shrink(false);
+    let caption = Label::new(None);
+    caption.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    caption.set_max_width_chars(30);
+    preview.append(&picture);
+    preview.append(&caption);
+    popover.set_child(Some(&preview));
+
+    let motion = EventControllerMotion::new();
+    {
+        let s = state.clone();
+        let pop = popover.clone();
+        motion.connect_enter(move |controller, _, _| {
+            let Some(row) = controller.widget() else {
+                return;
+            };
+            let Some((webview, url, title)) = s.try_borrow().ok().and_then(|state| state.tabs.iter()
+                .find(|t| t.row == row)
+                .map(|t| (
+                    t.webview.clone(),
+                    t.current_url(),
+                    t.row_label.text().to_string(),
+                )))
+            else {
+                return;
+            };
+
+            caption.set_text(&title);
+            caption.set_tooltip_text(Some(&url));
+            let cached = crate::thumbnails::with_thumbnails(|cache| cache.get(&url));
+            picture.set_paintable(cached.as_ref());
+            picture.set_visible(cached.is_some());
+            pop.popup();
+
+            // The visible tab can be captured live
+            let pic = picture.clone();
+            capture_thumbnail(&webview, move |thumbnail| {
+                if let Some(texture) = thumbnail {
+                    pic.set_paintable(Some(&texture));
+                    pic.set_visible(true);
+                }
+            });
+        });
+    }
+    {
+        let pop = popover.clone();
+        motion.connect_leave(move |_| pop.popdown());
+    }
+    row.add_controller(motion);
+
+    // Popovers must be unparented before their row goes away
+    row.connect_destroy(move |_| popover.unparent());
+}
+
+/// Turn address bar input into a URL: keep URLs, complete bare domains,
+/// search for everything else (with the keyword's engine, if any)
+fn resolve_input(text: &str) -> String {
+    if text.starts_with("fos://") {
+        // Typed by the user: actions may run
+        crate::protocol::typed_uri(text)
+    } else if text.starts_with("http") {
+        text.to_string()
+    } else if text.contains('.') && !text.contains(' ') {
+        format!("https://{}", text)
+    } else {
+        crate::search::search_url(text)
+    }
+}
+
+/// Load a URL in the active tab and hand focus to the page
+fn navigate(state: &Rc<RefCell<BrowserState>>, url: &str) {
+    let mut state = state.borrow_mut();
+    let idx = state.active_tab;
+    if idx < state.tabs.len() {
+        state.tabs[idx].webview.load_uri(url);
+        state.tabs[idx].url = url.to_string();
+        state.tabs[idx].loaded = true;
+        state.tabs[idx].webview.grab_focus();
+        update_tab_metrics(&state);
+    }
+}
+
+/// Whether keyboard focus is inside a widget
+fn has_focus_within(widget: &impl IsA<gtk4::Widget>) -> bool {
+    widget.root()
+        .and_then(|root| root.focus())
+        .is_some_and(|focus| focus.is_ancestor(widget) || focus == *widget.upcast_ref())
+}
+
+/// Omnibox dropdown for the address bar: suggestions update while typing,
+/// Up/Down picks one, Enter opens it, Escape dismisses
+fn attach_omnibox(state: &Rc<RefCell<BrowserState>>, address_bar: &Entry, tab_list: &ListBox) {
+    let popover = Popover::new();
+    popover.set_parent(address_bar);
+    popover.set_autohide(false);
+    popover.set_has_arrow(false);
+    // The address bar sits at the bottom of the window, so open upwards
+    popover.set_position(PositionType::Top);
+    popover.add_css_class("omnibox");
+
+    let list = ListBox::new();
+    list.set_selection_mode(SelectionMode::Single);
+    list.set_focusable(false);
+    popover.set_child(Some(&list));
+
+    let suggestions: Rc<RefCell<Vec<Suggestion>>> = Rc::default();
+
+    // Refresh suggestions while the user types
+    {
+        let s = state.clone();
+        let pop = popover.clone();
+        let lst = list.clone();
+        let sugg = suggestions.clone();
+        address_bar.connect_changed(move |entry| {
+            if !has_focus_within(entry) {
+                return;
+            }
+
+            let open_tabs: Vec<(String, String)> = s.try_borrow()
+                .map(|state| state.tabs.iter().map(|t| (
+                    t.current_url(),
+                    t.row_label.text().to_string(),
+                )).collect())
+                .unwrap_or_default();
+            let results = crate::omnibox::suggest(&entry.text(), &open_tabs);
+            if let Some(predicted) = crate::prefetch::predicted(&results) {
+                let session = s.try_borrow().ok()
+                    .and_then(|state| state.tabs.get(state.active_tab).and_then(|t| t.webview.network_session()));
+                if let Some(session) = session {
+                    crate::prefetch::prefetch(&session, &predicted.url);
+                }
+            }
+
+            while let Some(child) = lst.first_child() {
+                lst.remove(&child);
+            }
+            for suggestion in &results {
+                let icon = match suggestion.kind {
+                    SuggestionKind::OpenTab => "⧉",
+                    SuggestionKind::Bookmark => "★",
+                    SuggestionKind::History => "↺",
+                };
+                let text = if suggestion.title.is_empty() {
+                    format!("{}  {}", icon, suggestion.url)
+                } else {
+                    format!("{}  {} — {}", icon, suggestion.title, suggestion.url)
+                };
+                let label = Label::new(Some(&text));
+                label.set_halign(gtk4::Align::Start);
+                label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+                label.set_max_width_chars(80);
+                let row = ListBoxRow::new();
+                row.set_focusable(false);
+                row.set_child(Some(&label));
+                lst.append(&row);
+            }
+
+            if results.is_empty() {
+                pop.popdown();
+            } else {
+                pop.popup();
+            }
+            *sugg.borrow_mut() = results;
+        });
+    }
+
+    // Open a suggestion (switching to the tab if it is already open)
+    let open: Rc<dyn Fn(usize)> = {
+        let s = state.clone();
+        let tl = tab_list.clone();
+        let pop = popover.clone();
+        let sugg = suggestions.clone();
+        Rc::new(move |index| {
+            let Some(suggestion) = sugg.borrow().get(index).cloned() else {
+                return;
+            };
+            pop.popdown();
+
+            if suggestion.kind == SuggestionKind::OpenTab {
+                let row = s.borrow().tabs.iter()
+                    .find(|t| t.webview.uri().is_some_and(|u| u == suggestion.url.as_str()) || t.url == suggestion.url)
+                    .map(|t| t.row.clone());
+                if let Some(row) = row {
+                    tl.select_row(Some(&row));
+                    return;
+                }
+            }
+            navigate(&s, &suggestion.url);
+        })
+    };
+
+    {
+        let open = open.clone();
+        list.connect_row_activated(move |_, row| {
+            open(row.index() as usize);
+        });
+    }
+
+    // Keyboard navigation while typing
+    let keys = EventControllerKey::new();
+    keys.set_propagation_phase(PropagationPhase::Capture);
+    {
+        let pop = popover.clone();
+        let lst = list.clone();
+        keys.connect_key_pressed(move |_, key, _, _| {
+            use gtk4::gdk::Key;
+
+            if !pop.is_visible() {
+                return gtk4::glib::Propagation::Proceed;
+            }
+
+            let selected = lst.selected_row().map(|r| r.index()).unwrap_or(-1);
+            if key == Key::Down || key == Key::Up {
+                let next = if key == Key::Down { selected + 1 } else { selected - 1 };
+                match lst.row_at_index(next) {
+                    Some(row) => lst.select_row(Some(&row)),
+                    None if next < 0 => lst.unselect_all(),
+                    None => {}
+                }
+                return gtk4::glib::Propagation::Stop;
+            }
+            if key == Key::Escape {
+                pop.popdown();
+                return gtk4::glib::Propagation::Stop;
+            }
+            if (key == Key::Return || key == Key::KP_Enter) && selected >= 0 {
+                open(selected as usize);
+                return gtk4::glib::Propagation::Stop;
+            }
+            gtk4::glib::Propagation::Proceed
+        });
+    }
+    address_bar.add_controller(keys);
+
+    // Hide when focus leaves or the typed text is submitted
+    let focus = EventControllerFocus::new();
+    {
+        let pop = popover.clone();
+        focus.connect_leave(move |_| pop.popdown());
+    }
+    address_bar.add_controller(focus);
+    {
+        let pop = popover.clone();
+        address_bar.connect_activate(move |_| pop.popdown());
+    }
+}
+
+/// Widgets of the find bar (hidden until Ctrl+F)
+fn build_find_bar() -> FindBar {
+    let bar = GtkBox::new(Orientation::Horizontal, 4);
+    bar.set_margin_end(8);
+    bar.set_visible(false);
+
+    let entry = SearchEntry::new();
+    entry.set_placeholder_text(Some("Find in page"));
+    entry.set_width_chars(24);
+    let count = Label::new(None);
+    count.add_css_class("dim-label");
+    let previous = Button::with_label("‹");
+    previous.add_css_class("flat");
+    previous.set_tooltip_text(Some("Previous match (Shift+Enter)"));
+    let next = Button::with_label("›");
+    next.add_css_class("flat");
+    next.set_tooltip_text(Some("Next match (Enter)"));
+    {
+        let e = entry.clone();
+        previous.connect_clicked(move |_| e.emit_by_name::<()>("previous-match", &[]));
+        let e = entry.clone();
+        next.connect_clicked(move |_| e.emit_by_name::<()>("next-match", &[]));
+    }
+
+    bar.append(&entry);
+    bar.append(&previous);
+    bar.append(&next);
+    bar.append(&count);
+    FindBar { bar, entry, count }
+}
+
+/// Run a closure with the active tab's find controller
+fn with_find_controller(state: &Rc<RefCell<BrowserState>>, f: impl FnOnce(&webkit6::FindController)) {
+    let controller = {
+        let state = state.borrow();
+        state.tabs.get(state.active_tab).and_then(|t| t.webview.find_controller())
+    };
+    if let Some(controller) = controller {
+        f(&controller);
+    }
+}
+
+/// Show the find bar and focus it, searching again for any previous text
+fn open_find_bar(state: &Rc<RefCell<BrowserState>>) {
+    let (bar, entry) = {
+        let state = state.borrow();
+        (state.find_bar.bar.clone(), state.find_bar.entry.clone())
+    };
+    bar.set_visible(true);
+    entry.grab_focus();
+    entry.select_region(0, -1);
+    find_in_page(state, &entry.text());
+}
+
+/// Highlight every match of `text` in the active tab and jump to the first
+fn find_in_page(state: &Rc<RefCell<BrowserState>>, text: &str) {
+    let count = state.borrow().find_bar.count.clone();
+    if text.is_empty() {
+        count.set_text("");
+        with_find_controller(state, |fc| fc.search_finish());
+        return;
+    }
+
+    let options = (webkit6::FindOptions::CASE_INSENSITIVE | webkit6::FindOptions::WRAP_AROUND).bits();
+    with_find_controller(state, |fc| {
+        fc.count_matches(text, options, MAX_FIND_MATCHES);
+        fc.search(text, options, MAX_FIND_MATCHES);
+    });
+}
+
+/// Hide the find bar, clear highlights and return focus to the page
+fn close_find_bar(state: &Rc<RefCell<BrowserState>>) {
+    with_find_controller(state, |fc| fc.search_finish());
+    let state = state.borrow();
+    state.find_bar.bar.set_visible(false);
+    if let Some(tab) = state.tabs.get(state.active_tab) {
+        tab.webview.grab_focus();
+    }
+}
+
+/// Open the active tab's article in reader mode, or leave reader mode
+fn toggle_reader(state: &Rc<RefCell<BrowserState>>) {
+    let webview = {
+        let state = state.borrow();
+        let Some(tab) = state.tabs.get(state.active_tab) else {
+            return;
+        };
+        tab.webview.clone()
+    };
+
+    if webview.uri().is_some_and(|u| u.starts_with("fos://reader/")) {
+        webview.go_back();
+        return;
+    }
+
+    let wv = webview.clone();
+    crate::scripting::run(&webview, crate::reader::EXTRACT_SCRIPT, move |result| {
+        let article = match result {
+            Ok(json) => crate::reader::parse_article(&json),
+            Err(e) => {
+                warn!("Reader mode: {}", e);
+                return;
+            }
+        };
+        let Some(article) = article else {
+            info!("Reader mode: no article found");
+            return;
+        };
+        let tab = wv.page_id();
+        crate::reader::with_reader(|r| r.insert(tab, article));
+        wv.load_uri(&format!("fos://reader/{}", tab));
+    });
+}
+
+/// Show whether a URL is bookmarked on the star button
+fn refresh_star(star: &Button, url: &str) {
+    let bookmarked = crate::bookmarks::with_bookmarks(|b| b.is_bookmarked(url));
+    star.set_label(if bookmarked { "★" } else { "☆" });
+}
+
+/// Shield button with a panel of the active page's protections
+fn build_shield_button() -> MenuButton {
+    let button = MenuButton::new();
+    button.set_label("🛡");
+    button.add_css_class("flat");
+    button.set_tooltip_text(Some("Protections on this page"));
+
+    let panel = GtkBox::new(Orientation::Vertical, 4);
+    panel.add_css_class("shield");
+    let popover = Popover::new();
+    popover.set_child(Some(&panel));
+    button.set_popover(Some(&popover));
+    button
+}
+
+/// Show the active page's blocked count on the shield button
+fn refresh_shield(button: &MenuButton, webview: &WebView) {
+    let blocked = crate::shield::page(webview.page_id()).total_blocked();
+    button.set_label(&if blocked > 0 { format!("🛡 {}", blocked) } else { "🛡".to_string() });
+}
+
+/// Refresh the shield button if a web view is the active tab
+fn refresh_shield_if_active(state: &Rc<RefCell<BrowserState>>, webview: &WebView) {
+    // Events may fire while the state is borrowed (e.g. tab creation)
+    let Ok(state) = state.try_borrow() else {
+        return;
+    };
+    if state.tabs.get(state.active_tab).is_some_and(|t| &t.webview == webview) {
+        refresh_shield(&state.shield_button, webview);
+    }
+}
+
+/// Fill the shield panel for the active tab; toggles write through to the
+/// adblock settings and set `changed`
+fn populate_shield_panel(state: &Rc<RefCell<BrowserState>>, popover: &Popover, changed: &Rc<Cell<bool>>) {
+    let Some(webview) = ({
+        let s = state.borrow();
+        s.tabs.get(s.active_tab).map(|t| t.webview.clone())
+    }) else {
+        return;
+    };
+    let Some(panel) = popover.child().and_downcast::<GtkBox>() else {
+        return;
+    };
+    while let Some(child) = panel.first_child() {
+        panel.remove(&child);
+    }
+
+    let uri = webview.uri().map(|u| u.to_string()).unwrap_or_default();
+    let host = crate::omnibox::host_of(&uri).to_string();
+    let page = crate::shield::page(webview.page_id());
+    let config = crate::config::with_config(|c| c.adblock.clone());
+
+    let title = Label::new(Some(if host.is_empty() { "This page" } else { &host }));
+    title.add_css_class("heading");
+    title.set_halign(gtk4::Align::Start);
+    panel.append(&title);
+    let summary = Label::new(Some(&format!(
+        "{} requests blocked, {} cosmetic rules applied",
+        page.total_blocked(), page.cosmetic_rules
+    )));
+    summary.add_css_class("dim-label");
+    summary.set_halign(gtk4::Align::Start);
+    panel.append(&summary);
+
+    let toggle = |label: &str, active: bool, apply: Box<dyn Fn(bool)>| {
+        let check = CheckButton::with_label(label);
+        check.set_active(active);
+        let changed = changed.clone();
+        check.connect_toggled(move |check| {
+            apply(check.is_active());
+            changed.set(true);
+        });
+        panel.append(&check);
+    };
+
+    if uri.starts_with("http") {
+        let blocking = !crate::adblocker::with_blocking_prefs(|p| p.is_site_allowed(&uri));
+        let (s, url) = (state.clone(), uri.clone());
+        toggle("Block ads and trackers on this site", blocking, Box::new(move |on| {
+            crate::adblocker::with_blocking_prefs(|p| { p.set_site_allowed(&url, !on); p.save(); });
+            if let Ok(state) = s.try_borrow() {
+                refresh_site_toggle(&state.site_toggle, &url);
+            }
+        }));
+    }
+    if !host.is_empty() {
+        let protected = crate::config::with_config(|c| c.fingerprint.is_enabled_for(&uri));
+        toggle("Fingerprinting protection on this site", protected, Box::new(move |on| {
+            crate::config::with_config(|c| { c.fingerprint.set_site_enabled(&uri, on); c.save(); });
+            crate::fingerprint::refresh();
+        }));
+    }
+
+    let all_sites = Label::new(Some("All sites"));
+    all_sites.add_css_class("heading");
+    all_sites.set_halign(gtk4::Align::Start);
+    all_sites.set_margin_top(6);
+    panel.append(&all_sites);
+    toggle("Block ads and trackers", config.enabled, Box::new(|on| {
+        crate::config::with_config(|c| { c.adblock.enabled = on; c.save(); });
+    }));
+    toggle("Hide ad placeholders", config.cosmetic_filters, Box::new(|on| {
+        crate::config::with_config(|c| { c.adblock.cosmetic_filters = on; c.save(); });
+    }));
+    for category in crate::adblocker::TrackerCategory::ALL {
+        let enabled = crate::adblocker::with_blocking_prefs(|p| p.is_enabled(category));
+        let label = format!("{} ({} blocked here)", category.label(), page.blocked_in(category));
+        toggle(&label, enabled, Box::new(move |on| {
+            crate::adblocker::with_blocking_prefs(|p| { p.set_enabled(category, on); p.save(); });
+        }));
+    }
+}
+
+/// Show whether blocking is off for a page's site
+fn refresh_site_toggle(button: &Button, url: &str) {
+    let blockable = url.starts_with("http");
+    let allowed = crate::adblocker::with_blocking_prefs(|p| p.is_site_allowed(url));
+    button.set_sensitive(blockable);
+    button.set_label(if allowed { "▶" } else { "⏸" });
+    button.set_tooltip_text(Some(if allowed {
+        "Blocking is off on this site - click to turn it back on"
+    } else {
+        "Turn blocking off on this site"
+    }));
+}
+
+/// Turn blocking off for the active tab's site, or back on, and reload it
+fn toggle_site_blocking(state: &Rc<RefCell<BrowserState>>) {
+    let state = state.borrow();
+    let Some(tab) = state.tabs.get(state.active_tab) else {
+        return;
+    };
+    let Some(url) = tab.webview.uri().map(|u| u.to_string()).filter(|u| u.starts_with("http")) else {
+        return;
+    };
+
+    crate::adblocker::with_blocking_prefs(|prefs| {
+        let allowed = prefs.is_site_allowed(&url);
+        prefs.set_site_allowed(&url, !allowed);
+        prefs.save();
+        info!("Blocking {} for {}", if allowed { "on" } else { "off" }, url);
+    });
+    refresh_site_toggle(&state.site_toggle, &url);
+    tab.webview.reload();
+}
+
+/// Bookmark the active tab, or remove its bookmark if it already has one
+fn toggle_bookmark(state: &Rc<RefCell<BrowserState>>) {
+    let state = state.borrow();
+    let Some(tab) = state.tabs.get(state.active_tab) else {
+        return;
+    };
+
+    let url = tab.current_url();
+    let title = tab.webview.title()
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| tab.row_label.text().to_string());
+
+    let removed = crate::bookmarks::with_bookmarks(|store| {
+        let removed = store.find_by_url(&url).map(|b| b.id).and_then(|id| store.remove(id));
+        if removed.is_some() {
+            info!("Removed bookmark: {}", url);
+        } else {
+            store.add(&url, &title, None);
+            info!("Bookmarked: {}", url);
+        }
+        store.save();
+        removed
+    });
+    refresh_star(&state.star_button, &url);
+
+    if let Some(bookmark) = removed {
+        crate::undo::register("Bookmark removed", move || crate::bookmarks::with_bookmarks(|store| {
+            store.restore(Vec::new(), vec![bookmark]);
+            store.save();
+        }));
+    }
+}
+
 /// Browser wrapper
 pub struct WebBrowser;
 impl WebBrowser {
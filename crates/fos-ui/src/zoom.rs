@@ -0,0 +1,75 @@
+//! Zoom Module - Per-site zoom levels
+//!
+//! Remembers the zoom level chosen for each site (by host) and
+//! persists it as JSON in the data directory.
+
+use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::omnibox::host_of;
+
+/// Smallest allowed zoom level
+pub const MIN_ZOOM: f64 = 0.3;
+
+/// Largest allowed zoom level
+pub const MAX_ZOOM: f64 = 5.0;
+
+/// Zoom levels keyed by host
+#[derive(Serialize, Deserialize, Default)]
+pub struct ZoomStore {
+    sites: HashMap<String, f64>,
+}
+
+// Thread-local store (since we're running single-threaded GTK)
+thread_local! {
+    static ZOOM: RefCell<ZoomStore> = RefCell::new(ZoomStore::load());
+}
+
+/// Run a closure with the zoom store
+pub fn with_zoom<R>(f: impl FnOnce(&mut ZoomStore) -> R) -> R {
+    ZOOM.with(|store| f(&mut store.borrow_mut()))
+}
+
+impl ZoomStore {
+    /// Load zoom levels from the data directory
+    pub fn load() -> Self {
+        let path = crate::webview::get_data_dir().join("zoom.json");
+        if let Ok(data) = fs::read_to_string(&path) {
+            serde_json::from_str(&data).unwrap_or_default()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Save zoom levels to the data directory
+    pub fn save(&self) {
+        let path = crate::webview::get_data_dir().join("zoom.json");
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            fs::write(path, json).ok();
+        }
+    }
+
+    /// Zoom level for a URL's site (1.0 if never changed)
+    pub fn zoom_for(&self, url: &str) -> f64 {
+        self.sites.get(host_of(url)).copied().unwrap_or(1.0)
+    }
+
+    /// Remember the zoom level for a URL's site, returning the clamped level
+    pub fn set_zoom(&mut self, url: &str, level: f64) -> f64 {
+        let level = level.clamp(MIN_ZOOM, MAX_ZOOM);
+        let host = host_of(url);
+        if host.is_empty() {
+            return level;
+        }
+
+        // Back to 100% means "no preference"
+        if (level - 1.0).abs() < 0.01 {
+            self.sites.remove(host);
+        } else {
+            self.sites.insert(host.to_string(), level);
+        }
+        level
+    }
+}
@@ -2,8 +2,18 @@
 //!
 //! Main entry point for the browser. Initializes the global allocator,
 //! sets up logging, and launches the browser with system WebView.
+//!
+//! Maintenance commands (run without opening a window):
+//! - `--import-bookmarks <file>`: import a Netscape bookmark file
+//! - `--export-bookmarks <file>`: export bookmarks as a Netscape bookmark file
+//! - `--stats`: print the weekly adblock report
+//! - `--export-data <file>`: export all browser data as one archive
+//! - `--import-data <file> [section...]`: restore an archive (optionally only some sections)
+//!
+//! Any other arguments (`--help`, `--display`, ...) go to GTK.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::path::Path;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
@@ -11,6 +21,15 @@ use tracing_subscriber::FmtSubscriber;
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
+/// Maintenance commands; every other argument is left to GTK
+const COMMANDS: &[&str] = &[
+    "--stats",
+    "--import-bookmarks",
+    "--export-bookmarks",
+    "--export-data",
+    "--import-data",
+];
+
 fn main() -> Result<()> {
     // Initialize logging
     let _subscriber = FmtSubscriber::builder()
@@ -19,6 +38,13 @@ fn main() -> Result<()> {
         .compact()
         .init();
 
+    // Maintenance commands exit before GTK parses the command line
+    // (which handles --help, --display, --gapplication-service, ...)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first().filter(|a| COMMANDS.contains(&a.as_str())) {
+        return run_command(command, &args[1..]);
+    }
+
     info!("fOS-WB starting...");
     info!("Using mimalloc allocator");
     info!("Using system WebView for full web compatibility");
@@ -29,3 +55,35 @@ fn main() -> Result<()> {
     info!("fOS-WB shutting down");
     Ok(())
 }
+
+/// Run a maintenance command instead of the browser
+fn run_command(command: &str, args: &[String]) -> Result<()> {
+    let path = args.first().map(Path::new);
+    match (command, path) {
+        ("--stats", _) => {
+            print!("{}", fos_ui::weekly_report().to_text());
+        }
+        ("--import-bookmarks", Some(path)) => {
+            let count = fos_ui::import_bookmarks(path)?;
+            println!("Imported {} bookmarks from {}", count, path.display());
+        }
+        ("--export-bookmarks", Some(path)) => {
+            fos_ui::export_bookmarks(path)?;
+            println!("Exported bookmarks to {}", path.display());
+        }
+        ("--export-data", Some(path)) => {
+            let sections = fos_ui::export_archive(path)?;
+            println!("Exported {} to {}", sections.join(", "), path.display());
+        }
+        ("--import-data", Some(path)) => {
+            let sections = fos_ui::import_archive(path, &args[1..])?;
+            println!("Restored {} from {}", sections.join(", "), path.display());
+        }
+        _ => bail!(
+            "{} needs a file (archive sections for --import-data: {})",
+            command,
+            fos_ui::archive_sections()
+        ),
+    }
+    Ok(())
+}